@@ -54,8 +54,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 label: "nix-build -A pkgB".to_string(),
             },
         ]),
-        completed_at: Some("2018-01-01T01:01:01Z".to_string()),
-        started_at: Some("2018-08-01T01:01:01Z".to_string()),
+        completed_at: Some("2018-01-01T01:01:01Z".parse()?),
+        started_at: Some("2018-08-01T01:01:01Z".parse()?),
         conclusion: Some(Conclusion::Neutral),
         details_url: Some("https://nix.ci/status/hi".to_string()),
         external_id: Some("heyyy".to_string()),
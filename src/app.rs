@@ -1,7 +1,10 @@
 //! Labels interface
-use serde::Deserialize;
+use std::collections::BTreeMap;
 
-use self::super::{AuthenticationConstraint, Future, Github, MediaType};
+use serde::{Deserialize, Serialize};
+
+use self::super::{AuthenticationConstraint, Future, Github, MediaType, Timestamp};
+use crate::repositories::Repo;
 
 pub struct App {
     github: Github,
@@ -26,6 +29,23 @@ impl App {
         )
     }
 
+    /// Create an installation access token restricted to a subset of the repositories and
+    /// permissions of the installation
+    ///
+    /// https://docs.github.com/en/rest/apps/apps#create-an-installation-access-token-for-an-app
+    pub fn make_scoped_access_token(
+        &self,
+        installation_id: u64,
+        options: &AccessTokenOptions,
+    ) -> Future<AccessToken> {
+        self.github.post_media::<AccessToken>(
+            &self.path(&format!("/installations/{}/access_tokens", installation_id)),
+            json!(options),
+            MediaType::Preview("machine-man"),
+            AuthenticationConstraint::JWT,
+        )
+    }
+
     pub fn find_repo_installation<O, R>(&self, owner: O, repo: R) -> Future<Installation>
     where
         O: Into<String>,
@@ -43,7 +63,71 @@ impl App {
 #[derive(Debug, Deserialize)]
 pub struct AccessToken {
     pub token: String,
-    pub expires_at: String,
+    pub expires_at: Timestamp,
+    /// Permissions granted to the token, by name, e.g. `contents: read`
+    #[serde(default)]
+    pub permissions: BTreeMap<String, String>,
+    /// `all` or `selected`
+    pub repository_selection: Option<String>,
+    /// Repositories the token has access to, if restricted to selected ones
+    pub repositories: Option<Vec<Repo>>,
+}
+
+/// Restrictions of the repositories and permissions of an installation access token
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct AccessTokenOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repositories: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repository_ids: Option<Vec<u64>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    permissions: BTreeMap<String, String>,
+}
+
+impl AccessTokenOptions {
+    pub fn builder() -> AccessTokenOptionsBuilder {
+        AccessTokenOptionsBuilder::default()
+    }
+}
+
+/// builder interface for AccessTokenOptions
+#[derive(Default)]
+pub struct AccessTokenOptionsBuilder(AccessTokenOptions);
+
+impl AccessTokenOptionsBuilder {
+    /// Restrict the token to repositories with the given names, without owners
+    pub fn repositories<I, R>(&mut self, repositories: I) -> &mut Self
+    where
+        I: IntoIterator<Item = R>,
+        R: Into<String>,
+    {
+        self.0.repositories = Some(repositories.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Restrict the token to repositories with the given ids
+    pub fn repository_ids<I>(&mut self, repository_ids: I) -> &mut Self
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        self.0.repository_ids = Some(repository_ids.into_iter().collect());
+        self
+    }
+
+    /// Grant the token a permission at the given level, e.g. `permission("checks", "write")`.
+    /// Tokens are granted all permissions of the installation unless at least one is given
+    pub fn permission<N, L>(&mut self, name: N, level: L) -> &mut Self
+    where
+        N: Into<String>,
+        L: Into<String>,
+    {
+        self.0.permissions.insert(name.into(), level.into());
+        self
+    }
+
+    pub fn build(&self) -> AccessTokenOptions {
+        self.0.clone()
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -62,3 +146,123 @@ pub struct Installation {
     pub single_file_name: Option<String>,
     pub repository_selection: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{github, FakeTransport};
+    use crate::{Credentials, InstallationTokenGenerator, JWTCredentials};
+    use futures::prelude::*;
+    use http::header::AUTHORIZATION;
+
+    #[test]
+    fn access_token_options() {
+        let options = AccessTokenOptions::builder()
+            .repositories(vec!["hubcaps"])
+            .repository_ids(vec![1296269])
+            .permission("contents", "read")
+            .permission("checks", "write")
+            .build();
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            serde_json::json!({
+                "repositories": ["hubcaps"],
+                "repository_ids": [1296269],
+                "permissions": {"checks": "write", "contents": "read"},
+            })
+        );
+        assert_eq!(
+            serde_json::to_string(&AccessTokenOptions::default()).unwrap(),
+            "{}"
+        );
+    }
+
+    #[tokio::test]
+    async fn refreshes_installation_tokens() {
+        let transport = FakeTransport::new(vec![
+            (201, r#"{"token":"v1","expires_at":"2099-01-01T00:00:00Z"}"#),
+            (200, "{}"),
+            (200, "{}"),
+            (200, "{}"),
+            (401, r#"{"message":"Bad credentials"}"#),
+            (201, r#"{"token":"v2","expires_at":"2000-01-01T00:00:00Z"}"#),
+            (200, "{}"),
+            (201, r#"{"token":"v3","expires_at":"2099-01-01T00:00:00Z"}"#),
+            (200, "{}"),
+        ]);
+        let key = include_bytes!("../tests/fixtures/app_key.der").to_vec();
+        let generator = InstallationTokenGenerator::new(1, JWTCredentials::new(1, key).unwrap());
+        let mut github = github(&transport);
+        github.set_credentials(Credentials::InstallationToken(generator));
+
+        // concurrent requests finding the token stale share a single refresh
+        let users = (0..3).map(|_| github.get::<serde_json::Value>("/user"));
+        for user in future::join_all(users).await {
+            user.unwrap();
+        }
+        // a rejected token is refreshed and the request retried
+        github.get::<serde_json::Value>("/user").await.unwrap();
+        // a token about to expire is refreshed ahead of time
+        github.get::<serde_json::Value>("/user").await.unwrap();
+
+        let requests = transport.requests.lock().unwrap();
+        let requests = requests
+            .iter()
+            .map(|(method, uri, headers)| {
+                let auth = headers[AUTHORIZATION].to_str().unwrap();
+                match auth.strip_prefix("Bearer ") {
+                    Some(_) => format!("{} {} jwt", method, uri),
+                    None => format!("{} {} {}", method, uri, auth),
+                }
+            })
+            .collect::<Vec<_>>();
+        let refresh = "POST https://api.github.com/app/installations/1/access_tokens jwt";
+        assert_eq!(
+            requests,
+            vec![
+                refresh,
+                "GET https://api.github.com/user token v1",
+                "GET https://api.github.com/user token v1",
+                "GET https://api.github.com/user token v1",
+                "GET https://api.github.com/user token v1",
+                refresh,
+                "GET https://api.github.com/user token v2",
+                refresh,
+                "GET https://api.github.com/user token v3",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn scopes_installation_tokens() {
+        let token = r#"{"token":"v1","expires_at":"2099-01-01T00:00:00Z",
+            "permissions":{"checks":"write"},"repository_selection":"selected","repositories":[]}"#;
+        let transport = FakeTransport::new(vec![(201, token), (201, token), (200, "{}")]);
+        let key = include_bytes!("../tests/fixtures/app_key.der").to_vec();
+        let jwt = JWTCredentials::new(1, key).unwrap();
+        let scope = AccessTokenOptions::builder()
+            .repositories(vec!["hubcaps"])
+            .permission("checks", "write")
+            .build();
+        let mut github = github(&transport);
+        github.set_credentials(Credentials::JWT(jwt.clone()));
+
+        let access = github
+            .app()
+            .make_scoped_access_token(1, &scope)
+            .await
+            .unwrap();
+        assert_eq!(access.permissions["checks"], "write");
+        assert_eq!(access.repository_selection.as_deref(), Some("selected"));
+        assert_eq!(access.repositories.map(|repos| repos.len()), Some(0));
+
+        github.set_credentials(Credentials::InstallationToken(
+            InstallationTokenGenerator::scoped(1, jwt, scope),
+        ));
+        github.get::<serde_json::Value>("/user").await.unwrap();
+        let bodies = transport.bodies.lock().unwrap();
+        let expected = br#"{"repositories":["hubcaps"],"permissions":{"checks":"write"}}"#;
+        assert_eq!(bodies[0].as_deref(), Some(&expected[..]));
+        assert_eq!(bodies[1].as_deref(), Some(&expected[..]));
+    }
+}
@@ -0,0 +1,474 @@
+//! A blocking facade over the asynchronous client
+//!
+//! The types in this module mirror `Github` and the most commonly used services. Rather than
+//! futures and streams their operations return results and iterators, driving requests to
+//! completion on a runtime owned by the client. They are intended for programs which don't
+//! otherwise run an async runtime, like command line tools and build scripts, and must not be
+//! used from within one. Representation and options types are shared with the async API.
+//!
+//! Services not mirrored here are still usable through
+//! [Github#block_on](struct.Github.html#method.block_on) and
+//! [Github#iter](struct.Github.html#method.iter).
+//!
+//! This module requires the `blocking` feature.
+//!
+//! ```no_run
+//! use hubcaps::blocking::Github;
+//! use hubcaps::issues::IssueListOptions;
+//!
+//! # fn main() -> hubcaps::Result<()> {
+//! let github = Github::new("user-agent-name", None)?;
+//! for issue in github
+//!     .repo("softprops", "hubcaps")
+//!     .issues()
+//!     .iter(&IssueListOptions::builder().per_page(100).build())
+//! {
+//!     println!("{}", issue?.title);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use futures::prelude::*;
+
+use crate::comments::{self, Comment, CommentListOptions, CommentOptions};
+use crate::issues::{self, Issue, IssueListOptions, IssueOptions};
+use crate::labels::{self, Label, LabelOptions};
+use crate::pulls::{self, FileDiff, Pull, PullEditOptions, PullListOptions, PullOptions};
+use crate::repositories::{self, Repo, RepoEditOptions};
+use crate::statuses::{self, Status, StatusOptions};
+use crate::users::{self, AuthenticatedUser, User};
+use crate::{Credentials, Future, Result, Stream};
+
+/// A runtime shared by a client and all services created from it
+#[derive(Clone)]
+struct Runtime(Arc<Mutex<tokio::runtime::Runtime>>);
+
+impl Runtime {
+    fn new() -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new()
+            .basic_scheduler()
+            .enable_all()
+            .build()?;
+        Ok(Runtime(Arc::new(Mutex::new(runtime))))
+    }
+
+    fn block_on<T>(&self, future: Future<T>) -> Result<T> {
+        self.0.lock().unwrap().block_on(future)
+    }
+
+    fn iter<T>(&self, stream: Stream<T>) -> Iter<T> {
+        Iter {
+            stream,
+            runtime: self.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for Runtime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Runtime")
+    }
+}
+
+/// An iterator over all items of a paginated listing, fetching pages as they are needed
+pub struct Iter<T> {
+    stream: Stream<T>,
+    runtime: Runtime,
+}
+
+impl<T> Iterator for Iter<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let stream = &mut self.stream;
+        self.runtime.0.lock().unwrap().block_on(stream.next())
+    }
+}
+
+/// Blocking entry point interface for interacting with GitHub API
+#[derive(Clone, Debug)]
+pub struct Github {
+    github: crate::Github,
+    runtime: Runtime,
+}
+
+impl Github {
+    pub fn new<A, C>(agent: A, credentials: C) -> Result<Self>
+    where
+        A: Into<String>,
+        C: Into<Option<Credentials>>,
+    {
+        Self::from_async(crate::Github::new(agent, credentials)?)
+    }
+
+    pub fn host<H, A, C>(host: H, agent: A, credentials: C) -> Result<Self>
+    where
+        H: Into<String>,
+        A: Into<String>,
+        C: Into<Option<Credentials>>,
+    {
+        Self::from_async(crate::Github::host(host, agent, credentials)?)
+    }
+
+    /// Create a blocking client from an async one, keeping its configuration like
+    /// retry policies or middleware
+    pub fn from_async(github: crate::Github) -> Result<Self> {
+        Ok(Github {
+            github,
+            runtime: Runtime::new()?,
+        })
+    }
+
+    /// The async client requests are made with
+    pub fn as_async(&self) -> &crate::Github {
+        &self.github
+    }
+
+    /// Drive a future of the async API to completion
+    pub fn block_on<T>(&self, future: Future<T>) -> Result<T> {
+        self.runtime.block_on(future)
+    }
+
+    /// Turn a stream of the async API into an iterator
+    pub fn iter<T>(&self, stream: Stream<T>) -> Iter<T> {
+        self.runtime.iter(stream)
+    }
+
+    pub fn repo<O, R>(&self, owner: O, repo: R) -> Repository
+    where
+        O: Into<String>,
+        R: Into<String>,
+    {
+        Repository {
+            repo: self.github.repo(owner, repo),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn users(&self) -> Users {
+        Users {
+            users: self.github.users(),
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+/// Blocking counterpart of [repositories::Repository](../repositories/struct.Repository.html)
+pub struct Repository {
+    repo: repositories::Repository,
+    runtime: Runtime,
+}
+
+impl Repository {
+    /// Get the repository
+    pub fn get(&self) -> Result<Repo> {
+        self.runtime.block_on(self.repo.get())
+    }
+
+    /// Edit the repository
+    pub fn edit(&self, options: &RepoEditOptions) -> Result<Repo> {
+        self.runtime.block_on(self.repo.edit(options))
+    }
+
+    /// Delete the repository
+    pub fn delete(&self) -> Result<()> {
+        self.runtime.block_on(self.repo.delete())
+    }
+
+    /// Provides access to this repository's issues
+    pub fn issues(&self) -> Issues {
+        Issues {
+            issues: self.repo.issues(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Provides access to a single issue of this repository
+    pub fn issue(&self, number: u64) -> IssueRef {
+        IssueRef {
+            issue: self.repo.issue(number),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Provides access to this repository's labels
+    pub fn labels(&self) -> Labels {
+        Labels {
+            labels: self.repo.labels(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Provides access to this repository's pull requests
+    pub fn pulls(&self) -> PullRequests {
+        PullRequests {
+            pulls: self.repo.pulls(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Provides access to this repository's commit statuses
+    pub fn statuses(&self) -> Statuses {
+        Statuses {
+            statuses: self.repo.statuses(),
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+/// Blocking counterpart of [issues::Issues](../issues/struct.Issues.html)
+pub struct Issues {
+    issues: issues::Issues,
+    runtime: Runtime,
+}
+
+impl Issues {
+    pub fn get(&self, number: u64) -> IssueRef {
+        IssueRef {
+            issue: self.issues.get(number),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn create(&self, is: &IssueOptions) -> Result<Issue> {
+        self.runtime.block_on(self.issues.create(is))
+    }
+
+    /// Return the first page of issues for this repository
+    pub fn list(&self, options: &IssueListOptions) -> Result<Vec<Issue>> {
+        self.runtime.block_on(self.issues.list(options))
+    }
+
+    /// Return an iterator over all issues for this repository
+    pub fn iter(&self, options: &IssueListOptions) -> Iter<Issue> {
+        self.runtime.iter(self.issues.iter(options))
+    }
+}
+
+/// Blocking counterpart of [issues::IssueRef](../issues/struct.IssueRef.html)
+pub struct IssueRef {
+    issue: issues::IssueRef,
+    runtime: Runtime,
+}
+
+impl IssueRef {
+    /// Request an issue's information
+    pub fn get(&self) -> Result<Issue> {
+        self.runtime.block_on(self.issue.get())
+    }
+
+    /// Edit the issues options
+    pub fn edit(&self, is: &IssueOptions) -> Result<Issue> {
+        self.runtime.block_on(self.issue.edit(is))
+    }
+
+    /// Return a reference to labels operations available for this issue
+    pub fn labels(&self) -> IssueLabels {
+        IssueLabels {
+            labels: self.issue.labels(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Return a reference to comment operations available for this issue
+    pub fn comments(&self) -> Comments {
+        Comments {
+            comments: self.issue.comments(),
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+/// Blocking counterpart of [issues::IssueLabels](../issues/struct.IssueLabels.html)
+pub struct IssueLabels {
+    labels: issues::IssueLabels,
+    runtime: Runtime,
+}
+
+impl IssueLabels {
+    /// add a set of labels to this issue ref
+    pub fn add(&self, labels: Vec<&str>) -> Result<Vec<Label>> {
+        self.runtime.block_on(self.labels.add(labels))
+    }
+
+    /// remove a label from this issue
+    pub fn remove(&self, label: &str) -> Result<()> {
+        self.runtime.block_on(self.labels.remove(label))
+    }
+
+    /// replace all labels associated with this issue with a new set
+    pub fn set(&self, labels: Vec<&str>) -> Result<Vec<Label>> {
+        self.runtime.block_on(self.labels.set(labels))
+    }
+
+    /// remove all labels from an issue
+    pub fn clear(&self) -> Result<()> {
+        self.runtime.block_on(self.labels.clear())
+    }
+}
+
+/// Blocking counterpart of [comments::Comments](../comments/struct.Comments.html)
+pub struct Comments {
+    comments: comments::Comments,
+    runtime: Runtime,
+}
+
+impl Comments {
+    pub fn create(&self, comment: &CommentOptions) -> Result<Comment> {
+        self.runtime.block_on(self.comments.create(comment))
+    }
+
+    pub fn list(&self, options: &CommentListOptions) -> Result<Vec<Comment>> {
+        self.runtime.block_on(self.comments.list(options))
+    }
+}
+
+/// Blocking counterpart of [pulls::PullRequests](../pulls/struct.PullRequests.html)
+pub struct PullRequests {
+    pulls: pulls::PullRequests,
+    runtime: Runtime,
+}
+
+impl PullRequests {
+    /// Get a reference to a structure for interfacing with a specific pull request
+    pub fn get(&self, number: u64) -> PullRequest {
+        PullRequest {
+            pull: self.pulls.get(number),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Create a new pull request
+    pub fn create(&self, pr: &PullOptions) -> Result<Pull> {
+        self.runtime.block_on(self.pulls.create(pr))
+    }
+
+    /// list pull requests
+    pub fn list(&self, options: &PullListOptions) -> Result<Vec<Pull>> {
+        self.runtime.block_on(self.pulls.list(options))
+    }
+
+    /// provides an iterator over all pages of pull requests
+    pub fn iter(&self, options: &PullListOptions) -> Iter<Pull> {
+        self.runtime.iter(self.pulls.iter(options))
+    }
+}
+
+/// Blocking counterpart of [pulls::PullRequest](../pulls/struct.PullRequest.html)
+pub struct PullRequest {
+    pull: pulls::PullRequest,
+    runtime: Runtime,
+}
+
+impl PullRequest {
+    /// Request a pull requests information
+    pub fn get(&self) -> Result<Pull> {
+        self.runtime.block_on(self.pull.get())
+    }
+
+    /// short hand for editing state = open
+    pub fn open(&self) -> Result<Pull> {
+        self.runtime.block_on(self.pull.open())
+    }
+
+    /// shorthand for editing state = closed
+    pub fn close(&self) -> Result<Pull> {
+        self.runtime.block_on(self.pull.close())
+    }
+
+    /// Edit a pull request
+    pub fn edit(&self, pr: &PullEditOptions) -> Result<Pull> {
+        self.runtime.block_on(self.pull.edit(pr))
+    }
+
+    /// Returns a vector of file diffs associated with this pull
+    pub fn files(&self) -> Result<Vec<FileDiff>> {
+        self.runtime.block_on(self.pull.files())
+    }
+
+    /// Return a reference to labels operations available for this pull request
+    pub fn labels(&self) -> IssueLabels {
+        IssueLabels {
+            labels: self.pull.labels(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// returns issue comments interface
+    pub fn comments(&self) -> Comments {
+        Comments {
+            comments: self.pull.comments(),
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+/// Blocking counterpart of [labels::Labels](../labels/struct.Labels.html)
+pub struct Labels {
+    labels: labels::Labels,
+    runtime: Runtime,
+}
+
+impl Labels {
+    pub fn create(&self, lab: &LabelOptions) -> Result<Label> {
+        self.runtime.block_on(self.labels.create(lab))
+    }
+
+    pub fn update(&self, prevname: &str, lab: &LabelOptions) -> Result<Label> {
+        self.runtime.block_on(self.labels.update(prevname, lab))
+    }
+
+    pub fn delete(&self, name: &str) -> Result<()> {
+        self.runtime.block_on(self.labels.delete(name))
+    }
+
+    pub fn list(&self) -> Result<Vec<Label>> {
+        self.runtime.block_on(self.labels.list())
+    }
+
+    /// provides an iterator over all pages of this repo's labels
+    pub fn iter(&self) -> Iter<Label> {
+        self.runtime.iter(self.labels.iter())
+    }
+}
+
+/// Blocking counterpart of [statuses::Statuses](../statuses/struct.Statuses.html)
+pub struct Statuses {
+    statuses: statuses::Statuses,
+    runtime: Runtime,
+}
+
+impl Statuses {
+    /// creates a new status for a target sha
+    pub fn create(&self, sha: &str, status: &StatusOptions) -> Result<Status> {
+        self.runtime.block_on(self.statuses.create(sha, status))
+    }
+
+    /// lists all statuses associated with a given git sha
+    pub fn list(&self, sha: &str) -> Result<Vec<Status>> {
+        self.runtime.block_on(self.statuses.list(sha))
+    }
+}
+
+/// Blocking counterpart of [users::Users](../users/struct.Users.html)
+pub struct Users {
+    users: users::Users,
+    runtime: Runtime,
+}
+
+impl Users {
+    /// Information about current authenticated user
+    pub fn authenticated(&self) -> Result<AuthenticatedUser> {
+        self.runtime.block_on(self.users.authenticated())
+    }
+
+    pub fn get<U>(&self, username: U) -> Result<User>
+    where
+        U: Into<String>,
+    {
+        self.runtime.block_on(self.users.get(username))
+    }
+}
@@ -0,0 +1,420 @@
+//! Record and replay GitHub API interactions
+//!
+//! Tests exercising a `Github` client normally need network access to GitHub and a token. A
+//! [RecordingTransport](struct.RecordingTransport.html) instead writes every request and
+//! response made through a client to a cassette file. A
+//! [ReplayTransport](struct.ReplayTransport.html) later serves the recorded responses offline,
+//! so the same tests can run in CI.
+//!
+//! Recorded `Authorization` headers, `client_secret` query parameters and secrets in JSON
+//! bodies, like the tokens of OAuth and app installation token responses, are redacted before
+//! they are written to disk.
+//!
+//! ```no_run
+//! use hubcaps::{cassette, Credentials, Github};
+//!
+//! # fn main() -> hubcaps::Result<()> {
+//! let mut github = Github::new(
+//!     "user-agent-name",
+//!     std::env::var("GITHUB_TOKEN").ok().map(Credentials::Token),
+//! )?;
+//! // records when HUBCAPS_RECORD=1 is set, replays otherwise
+//! github.set_transport(cassette::from_env("tests/cassettes/labels.json")?);
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use futures::prelude::*;
+use http::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use http::{Request, Response};
+use log::debug;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::transport::{BoxedTransport, ReqwestTransport, Transport};
+use crate::{Error, Future, Result};
+
+/// Environment variable which makes [from_env](fn.from_env.html) record instead of replay
+pub const RECORD_ENV_VAR: &str = "HUBCAPS_RECORD";
+
+const REDACTED: &str = "REDACTED";
+
+/// Keys of JSON body members holding secrets
+const SECRET_KEYS: &[&str] = &["access_token", "client_secret", "refresh_token", "token"];
+
+/// Return a transport which records to the cassette at `path` if the `HUBCAPS_RECORD`
+/// environment variable is set to `1` or `true`, and otherwise replays from it
+pub fn from_env<P>(path: P) -> Result<BoxedTransport>
+where
+    P: Into<PathBuf>,
+{
+    match env::var(RECORD_ENV_VAR).as_ref().map(String::as_str) {
+        Ok("1") | Ok("true") => Ok(Box::new(RecordingTransport::new(
+            path,
+            ReqwestTransport::new(Client::builder().build()?),
+        ))),
+        _ => Ok(Box::new(ReplayTransport::load(path)?)),
+    }
+}
+
+/// A recorded series of request and response pairs
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load<P>(path: P) -> Result<Cassette>
+    where
+        P: AsRef<Path>,
+    {
+        let contents = fs::read(path)?;
+        Ok(serde_json::from_slice(&contents)?)
+    }
+
+    pub fn save<P>(&self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub uri: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: Option<RecordedBody>,
+}
+
+impl RecordedRequest {
+    fn new(request: &Request<Option<Vec<u8>>>) -> Self {
+        let mut headers = recorded_headers(request.headers());
+        if headers.contains_key(AUTHORIZATION.as_str()) {
+            headers.insert(AUTHORIZATION.as_str().into(), REDACTED.into());
+        }
+        RecordedRequest {
+            method: request.method().to_string(),
+            uri: redact_uri(&request.uri().to_string()),
+            headers,
+            body: request
+                .body()
+                .as_ref()
+                .map(|body| RecordedBody::new(&redact_body(body))),
+        }
+    }
+
+    /// Requests match on their method, uri and body. Headers are informational only
+    fn matches(&self, other: &RecordedRequest) -> bool {
+        self.method == other.method && self.uri == other.uri && self.body == other.body
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub body: RecordedBody,
+}
+
+impl RecordedResponse {
+    fn new(response: &Response<Vec<u8>>) -> Self {
+        RecordedResponse {
+            status: response.status().as_u16(),
+            headers: recorded_headers(response.headers()),
+            body: RecordedBody::new(&redact_body(response.body())),
+        }
+    }
+
+    fn to_response(&self) -> Result<Response<Vec<u8>>> {
+        let mut response = Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            response = response.header(name.as_str(), value.as_str());
+        }
+        Ok(response.body(self.body.to_bytes()?)?)
+    }
+}
+
+/// A message body, stored as text when it is valid utf-8 and base64 encoded otherwise
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordedBody {
+    Text(String),
+    Base64(String),
+}
+
+impl RecordedBody {
+    fn new(body: &[u8]) -> Self {
+        match String::from_utf8(body.to_vec()) {
+            Ok(text) => RecordedBody::Text(text),
+            Err(_) => RecordedBody::Base64(base64::encode(body)),
+        }
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            RecordedBody::Text(text) => Ok(text.clone().into_bytes()),
+            RecordedBody::Base64(encoded) => base64::decode(encoded).map_err(|err| {
+                Error::IO(std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+            }),
+        }
+    }
+}
+
+/// A transport which records all interactions with an inner transport to a cassette file
+///
+/// The cassette is rewritten after every interaction, so it is complete even if the test
+/// recording it fails halfway.
+#[derive(Clone, Debug)]
+pub struct RecordingTransport {
+    inner: BoxedTransport,
+    path: PathBuf,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl RecordingTransport {
+    pub fn new<P, T>(path: P, inner: T) -> Self
+    where
+        P: Into<PathBuf>,
+        T: Transport + Send + Sync + 'static,
+    {
+        RecordingTransport {
+            inner: Box::new(inner),
+            path: path.into(),
+            cassette: Arc::new(Mutex::new(Cassette::default())),
+        }
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn send(&self, request: Request<Option<Vec<u8>>>) -> Future<Response<Vec<u8>>> {
+        let recorded = RecordedRequest::new(&request);
+        let path = self.path.clone();
+        let cassette = self.cassette.clone();
+        Box::pin(self.inner.send(request).and_then(|response| async move {
+            let mut cassette = cassette.lock().unwrap();
+            cassette.interactions.push(Interaction {
+                request: recorded,
+                response: RecordedResponse::new(&response),
+            });
+            debug!("recording interaction to {}", path.display());
+            cassette.save(&path)?;
+            Ok(response)
+        }))
+    }
+}
+
+/// A transport which serves responses from a cassette file without touching the network
+///
+/// Each recorded interaction is served at most once, in the order they were recorded.
+/// A request without a matching recorded interaction fails with an `Error::IO` of kind
+/// `NotFound` naming its method and url.
+#[derive(Clone, Debug)]
+pub struct ReplayTransport {
+    path: PathBuf,
+    interactions: Arc<Mutex<Vec<Option<Interaction>>>>,
+}
+
+impl ReplayTransport {
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let cassette = Cassette::load(&path)?;
+        Ok(ReplayTransport {
+            path,
+            interactions: Arc::new(Mutex::new(
+                cassette.interactions.into_iter().map(Some).collect(),
+            )),
+        })
+    }
+
+    /// Returns true if every recorded interaction has been served
+    pub fn is_exhausted(&self) -> bool {
+        self.interactions
+            .lock()
+            .unwrap()
+            .iter()
+            .all(Option::is_none)
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn send(&self, request: Request<Option<Vec<u8>>>) -> Future<Response<Vec<u8>>> {
+        let recorded = RecordedRequest::new(&request);
+        let interaction = self
+            .interactions
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|interaction| match interaction {
+                Some(interaction) => interaction.request.matches(&recorded),
+                None => false,
+            })
+            .and_then(Option::take);
+        let response = match interaction {
+            Some(interaction) => interaction.response.to_response(),
+            None => Err(Error::IO(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "no unused interaction in {} matches {} {}",
+                    self.path.display(),
+                    recorded.method,
+                    recorded.uri
+                ),
+            ))),
+        };
+        Box::pin(future::ready(response))
+    }
+}
+
+fn recorded_headers(headers: &HeaderMap<HeaderValue>) -> BTreeMap<String, String> {
+    let mut recorded = BTreeMap::new();
+    for name in headers.keys() {
+        let values = headers
+            .get_all(name)
+            .iter()
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+            .collect::<Vec<_>>();
+        recorded.insert(name.as_str().to_owned(), values.join(", "));
+    }
+    recorded
+}
+
+fn redact_uri(uri: &str) -> String {
+    match Url::parse(uri) {
+        Ok(mut url) if url.query_pairs().any(|(key, _)| key == "client_secret") => {
+            let pairs = url
+                .query_pairs()
+                .map(|(key, value)| {
+                    let value = if key == "client_secret" {
+                        REDACTED.into()
+                    } else {
+                        value.into_owned()
+                    };
+                    (key.into_owned(), value)
+                })
+                .collect::<Vec<_>>();
+            url.query_pairs_mut().clear().extend_pairs(pairs);
+            url.to_string()
+        }
+        _ => uri.to_owned(),
+    }
+}
+
+/// Redact the values of secret members of a JSON body, leaving other bodies untouched
+fn redact_body(body: &[u8]) -> Vec<u8> {
+    fn redact(value: &mut serde_json::Value) -> bool {
+        match value {
+            serde_json::Value::Object(members) => {
+                let mut redacted = false;
+                for (key, value) in members.iter_mut() {
+                    if SECRET_KEYS.contains(&key.as_str()) && value.is_string() {
+                        *value = REDACTED.into();
+                        redacted = true;
+                    } else {
+                        redacted |= redact(value);
+                    }
+                }
+                redacted
+            }
+            serde_json::Value::Array(values) => values
+                .iter_mut()
+                .fold(false, |redacted, value| redact(value) | redacted),
+            _ => false,
+        }
+    }
+    match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(mut value) => {
+            if redact(&mut value) {
+                serde_json::to_vec(&value).unwrap_or_default()
+            } else {
+                body.to_vec()
+            }
+        }
+        Err(_) => body.to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_credentials() {
+        let request = Request::builder()
+            .uri("https://api.github.com/user?client_id=id&client_secret=secret")
+            .header(AUTHORIZATION, "token secret")
+            .body(None)
+            .unwrap();
+        let recorded = RecordedRequest::new(&request);
+        assert_eq!(
+            recorded.uri,
+            "https://api.github.com/user?client_id=id&client_secret=REDACTED"
+        );
+        assert_eq!(recorded.headers["authorization"], "REDACTED");
+    }
+
+    #[test]
+    fn redacts_secrets_in_bodies() {
+        let request = Request::builder()
+            .method("POST")
+            .uri("https://github.com/login/oauth/access_token")
+            .body(Some(
+                br#"{"client_id":"id","client_secret":"secret","refresh_token":"ghr_1"}"#.to_vec(),
+            ))
+            .unwrap();
+        let recorded = RecordedRequest::new(&request);
+        assert_eq!(
+            recorded.body,
+            Some(RecordedBody::Text(
+                r#"{"client_id":"id","client_secret":"REDACTED","refresh_token":"REDACTED"}"#
+                    .into()
+            ))
+        );
+        // replayed requests match on the redacted form
+        assert!(recorded.matches(&RecordedRequest::new(&request)));
+
+        let response = Response::builder()
+            .body(br#"[{"token":"ghs_1","permissions":{"checks":"write"}}]"#.to_vec())
+            .unwrap();
+        assert_eq!(
+            RecordedResponse::new(&response).body,
+            RecordedBody::Text(r#"[{"permissions":{"checks":"write"},"token":"REDACTED"}]"#.into())
+        );
+        let listing = br#"[{"name":"hubcaps"}]"#;
+        assert_eq!(redact_body(listing), listing.to_vec());
+    }
+
+    #[test]
+    fn bodies_roundtrip() {
+        for body in &[b"{}".to_vec(), vec![0xff, 0xfe]] {
+            assert_eq!(&RecordedBody::new(body).to_bytes().unwrap(), body);
+        }
+        assert_eq!(
+            RecordedBody::new(&[0xff]),
+            RecordedBody::Base64("/w==".into())
+        );
+    }
+}
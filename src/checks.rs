@@ -2,7 +2,7 @@
 // see: https://developer.github.com/v3/checks/suites/
 use serde::{Deserialize, Serialize};
 
-use self::super::{AuthenticationConstraint, Future, Github, MediaType};
+use self::super::{AuthenticationConstraint, Future, Github, MediaType, Timestamp};
 
 pub struct CheckRuns {
     github: Github,
@@ -10,7 +10,7 @@ pub struct CheckRuns {
     repo: String,
 }
 
-impl<'a> CheckRuns {
+impl CheckRuns {
     #[doc(hidden)]
     pub(crate) fn new<O, R>(github: Github, owner: O, repo: R) -> Self
     where
@@ -148,11 +148,11 @@ pub struct CheckRunOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<CheckRunState>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub started_at: Option<String>,
+    pub started_at: Option<Timestamp>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub conclusion: Option<Conclusion>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub completed_at: Option<String>,
+    pub completed_at: Option<Timestamp>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output: Option<Output>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -170,11 +170,11 @@ pub struct CheckRunUpdateOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<CheckRunState>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub started_at: Option<String>,
+    pub started_at: Option<Timestamp>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub conclusion: Option<Conclusion>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub completed_at: Option<String>,
+    pub completed_at: Option<Timestamp>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output: Option<Output>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -191,9 +191,9 @@ pub struct CheckRun {
     pub details_url: Option<String>,
     pub external_id: Option<String>,
     pub status: Option<CheckRunState>,
-    pub started_at: Option<String>,
+    pub started_at: Option<Timestamp>,
     pub conclusion: Option<Conclusion>,
-    pub completed_at: Option<String>,
+    pub completed_at: Option<Timestamp>,
     /*
     Deleted for now:
 
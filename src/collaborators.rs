@@ -5,19 +5,14 @@ use futures::prelude::*;
 use http::StatusCode;
 use std::collections::HashMap;
 use std::fmt;
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub enum Permissions {
     Admin,
+    #[default]
     Push,
     Pull,
 }
 
-impl Default for Permissions {
-    fn default() -> Permissions {
-        Permissions::Push
-    }
-}
-
 impl fmt::Display for Permissions {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use url::form_urlencoded;
 
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Timestamp};
 
 /// A structure for interfacing with a issue comments
 pub struct Comments {
@@ -61,8 +61,8 @@ pub struct Comment {
     pub html_url: String,
     pub body: String,
     pub user: User,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
 }
 
 #[derive(Debug, Serialize)]
@@ -99,9 +99,9 @@ pub struct CommentListOptionsBuilder(CommentListOptions);
 impl CommentListOptionsBuilder {
     pub fn since<S>(&mut self, since: S) -> &mut Self
     where
-        S: Into<String>,
+        S: Into<Timestamp>,
     {
-        self.0.params.insert("since", since.into());
+        self.0.params.insert("since", since.into().to_string());
         self
     }
 
@@ -6,7 +6,7 @@ use url::form_urlencoded;
 
 use crate::statuses::State;
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Timestamp};
 
 /// Interface for repository deployments
 pub struct Deployments {
@@ -115,8 +115,8 @@ pub struct Deployment {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub creator: User,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
     pub statuses_url: String,
     pub repository_url: String,
 }
@@ -221,8 +221,8 @@ impl DeploymentOptionsBuilder {
 #[derive(Debug, Deserialize)]
 pub struct DeploymentStatus {
     pub url: String,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
     pub state: State,
     pub target_url: Option<String>,
     pub description: Option<String>,
@@ -0,0 +1,374 @@
+//! Credential discovery
+//!
+//! [Credentials::discover](../enum.Credentials.html#method.discover) looks for a token for a
+//! GitHub host in the places command line tools conventionally keep them, in order:
+//!
+//! 1. the `GH_TOKEN` and `GITHUB_TOKEN` environment variables, or `GH_ENTERPRISE_TOKEN` and
+//!    `GITHUB_ENTERPRISE_TOKEN` for GitHub Enterprise hosts
+//! 2. the `hosts.yml` file of the [gh](https://cli.github.com) command line tool
+//! 3. the `.netrc` file in the home directory, or the file named by `NETRC`
+//! 4. the credential helpers configured for git, through `git credential fill`
+//!
+//! ```no_run
+//! use hubcaps::{Credentials, Github};
+//!
+//! # fn main() -> hubcaps::Result<()> {
+//! let host = "https://api.github.com";
+//! let discovered = Credentials::discover(host);
+//! if let Some(discovered) = &discovered {
+//!     println!("using credentials from {}", discovered.source);
+//! }
+//! let github = Github::host(host, "user-agent-name", discovered.map(|d| d.credentials))?;
+//! # Ok(())
+//! # }
+//! ```
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use log::debug;
+
+use crate::Credentials;
+
+/// Where discovered credentials were found
+#[derive(Clone, Debug, PartialEq)]
+pub enum CredentialSource {
+    /// An environment variable, by name
+    Env(String),
+    /// The `hosts.yml` file of the gh command line tool
+    GhConfig(PathBuf),
+    /// A `.netrc` file
+    Netrc(PathBuf),
+    /// `git credential fill`
+    GitCredential,
+}
+
+impl fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialSource::Env(name) => write!(f, "the {} environment variable", name),
+            CredentialSource::GhConfig(path) | CredentialSource::Netrc(path) => {
+                write!(f, "{}", path.display())
+            }
+            CredentialSource::GitCredential => write!(f, "git credential fill"),
+        }
+    }
+}
+
+/// Credentials along with where they were found
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiscoveredCredentials {
+    pub credentials: Credentials,
+    pub source: CredentialSource,
+}
+
+impl Credentials {
+    /// Look for a token for `host` in the environment and local configuration. See
+    /// [discovery](discovery/index.html) for the places looked in
+    ///
+    /// `host` may be an API host as passed to `Github::host`, like `https://api.github.com` or
+    /// `https://github.example.com/api/v3`, or a bare host name like `github.com`.
+    pub fn discover(host: &str) -> Option<DiscoveredCredentials> {
+        Discovery {
+            var: |name: &str| env::var(name).ok(),
+            home: home_dir(),
+            git: true,
+        }
+        .discover(host)
+    }
+}
+
+/// The environment credentials are discovered in
+struct Discovery<V> {
+    var: V,
+    home: Option<PathBuf>,
+    git: bool,
+}
+
+impl<V> Discovery<V>
+where
+    V: Fn(&str) -> Option<String>,
+{
+    fn discover(&self, host: &str) -> Option<DiscoveredCredentials> {
+        let host = host_name(host);
+        let (token, source) = self
+            .env(&host)
+            .or_else(|| self.gh_config(&host))
+            .or_else(|| self.netrc(&host))
+            .or_else(|| self.git_credential(&host))?;
+        debug!("Discovered credentials for {} in {}", host, source);
+        Some(DiscoveredCredentials {
+            credentials: Credentials::Token(token),
+            source,
+        })
+    }
+
+    fn env(&self, host: &str) -> Option<(String, CredentialSource)> {
+        let names: &[&str] = if host == "github.com" {
+            &["GH_TOKEN", "GITHUB_TOKEN"]
+        } else {
+            &["GH_ENTERPRISE_TOKEN", "GITHUB_ENTERPRISE_TOKEN"]
+        };
+        names.iter().find_map(|name| {
+            (self.var)(name)
+                .filter(|token| !token.is_empty())
+                .map(|token| (token, CredentialSource::Env((*name).to_owned())))
+        })
+    }
+
+    fn gh_config(&self, host: &str) -> Option<(String, CredentialSource)> {
+        let dir = match (self.var)("GH_CONFIG_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => match (self.var)("XDG_CONFIG_HOME") {
+                Some(dir) => Path::new(&dir).join("gh"),
+                None => self.home.as_ref()?.join(".config").join("gh"),
+            },
+        };
+        let path = dir.join("hosts.yml");
+        let token = gh_hosts_token(&fs::read_to_string(&path).ok()?, host)?;
+        Some((token, CredentialSource::GhConfig(path)))
+    }
+
+    fn netrc(&self, host: &str) -> Option<(String, CredentialSource)> {
+        let path = match (self.var)("NETRC") {
+            Some(path) => PathBuf::from(path),
+            None => self.home.as_ref()?.join(".netrc"),
+        };
+        let netrc = fs::read_to_string(&path).ok()?;
+        let token = netrc_password(&netrc, host)
+            .or_else(|| netrc_password(&netrc, &format!("api.{}", host)))?;
+        Some((token, CredentialSource::Netrc(path)))
+    }
+
+    fn git_credential(&self, host: &str) -> Option<(String, CredentialSource)> {
+        if !self.git {
+            return None;
+        }
+        let mut child = Command::new("git")
+            .args(["credential", "fill"])
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let input = format!("protocol=https\nhost={}\n\n", host);
+        child.stdin.take()?.write_all(input.as_bytes()).ok()?;
+        let output = child.wait_with_output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8(output.stdout)
+            .ok()?
+            .lines()
+            .find_map(|line| line.strip_prefix("password="))
+            .filter(|token| !token.is_empty())
+            .map(|token| (token.to_owned(), CredentialSource::GitCredential))
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+/// The name of the GitHub host serving an API host, e.g. `github.com` for
+/// `https://api.github.com`
+fn host_name(host: &str) -> String {
+    let host = host.split("://").last().unwrap_or(host);
+    let host = host.split('/').next().unwrap_or(host).to_lowercase();
+    match host.strip_prefix("api.") {
+        Some("github.com") => "github.com".to_owned(),
+        _ => host,
+    }
+}
+
+/// Read the token of a host from a gh `hosts.yml` file, which looks like
+///
+/// ```yaml
+/// github.com:
+///     user: octocat
+///     oauth_token: gho_...
+/// ```
+///
+/// Only this shape is understood, rather than YAML in general. Tokens kept in the system
+/// keyring by newer versions of gh aren't in the file at all.
+fn gh_hosts_token(hosts: &str, host: &str) -> Option<String> {
+    let mut lines = hosts
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .skip_while(|line| {
+            line.starts_with(char::is_whitespace) || unquote(line.trim_end_matches(':')) != host
+        })
+        .skip(1);
+    let first = lines.next()?;
+    let indent = first.len() - first.trim_start().len();
+    if indent == 0 {
+        return None;
+    }
+    std::iter::once(first)
+        .chain(lines)
+        .take_while(|line| line.starts_with(char::is_whitespace))
+        .filter(|line| line.len() - line.trim_start().len() == indent)
+        .find_map(|line| {
+            let (key, value) = line.trim().split_once(':')?;
+            Some(unquote(value.trim()).to_owned()).filter(|_| key == "oauth_token")
+        })
+        .filter(|token| !token.is_empty())
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim_matches(|c| c == '"' || c == '\'')
+}
+
+/// Read the password of a machine from a `.netrc` file
+fn netrc_password(netrc: &str, machine: &str) -> Option<String> {
+    let mut tokens = netrc.split_whitespace();
+    let mut matched = false;
+    let mut default = None;
+    let mut in_default = false;
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" => {
+                matched = tokens.next() == Some(machine);
+                in_default = false;
+            }
+            "default" => {
+                matched = false;
+                in_default = true;
+            }
+            "password" => {
+                let password = tokens.next()?;
+                if matched {
+                    return Some(password.to_owned());
+                }
+                if in_default {
+                    default = Some(password.to_owned());
+                }
+            }
+            _ => (),
+        }
+    }
+    default
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn normalizes_hosts() {
+        assert_eq!(host_name("https://api.github.com"), "github.com");
+        assert_eq!(host_name("github.com"), "github.com");
+        assert_eq!(
+            host_name("https://GHE.example.com/api/v3"),
+            "ghe.example.com"
+        );
+    }
+
+    #[test]
+    fn reads_gh_hosts() {
+        let hosts = "\
+github.com:
+    users:
+        octocat:
+            oauth_token: gho_nested
+    user: octocat
+    oauth_token: gho_github
+    git_protocol: https
+\"ghe.example.com\":
+  oauth_token: 'gho_enterprise'
+";
+        assert_eq!(
+            gh_hosts_token(hosts, "github.com").as_deref(),
+            Some("gho_github")
+        );
+        assert_eq!(
+            gh_hosts_token(hosts, "ghe.example.com").as_deref(),
+            Some("gho_enterprise")
+        );
+        assert_eq!(gh_hosts_token(hosts, "example.com"), None);
+    }
+
+    #[test]
+    fn reads_netrc() {
+        let netrc = "machine ghe.example.com login octocat password ghp_enterprise\n\
+                     machine api.github.com\n  login octocat\n  password ghp_github\n\
+                     default login anonymous password ghp_default";
+        assert_eq!(
+            netrc_password(netrc, "api.github.com").as_deref(),
+            Some("ghp_github")
+        );
+        assert_eq!(
+            netrc_password(netrc, "ghe.example.com").as_deref(),
+            Some("ghp_enterprise")
+        );
+        assert_eq!(
+            netrc_password(netrc, "example.com").as_deref(),
+            Some("ghp_default")
+        );
+    }
+
+    #[test]
+    fn discovers_credentials_in_order() {
+        let home = env::temp_dir().join(format!("hubcaps-discovery-{}", std::process::id()));
+        fs::create_dir_all(home.join(".config/gh")).unwrap();
+        fs::write(
+            home.join(".config/gh/hosts.yml"),
+            "github.com:\n    oauth_token: gho_gh\n",
+        )
+        .unwrap();
+        fs::write(
+            home.join(".netrc"),
+            "machine ghe.example.com password ghp_netrc",
+        )
+        .unwrap();
+        let discover = |vars: &[(&str, &str)], host: &str| {
+            let vars = vars
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>();
+            Discovery {
+                var: |name: &str| vars.get(name).cloned(),
+                home: Some(home.clone()),
+                git: false,
+            }
+            .discover(host)
+        };
+
+        let discovered = discover(&[("GITHUB_TOKEN", "ghp_env")], "https://api.github.com");
+        assert_eq!(
+            discovered,
+            Some(DiscoveredCredentials {
+                credentials: Credentials::Token("ghp_env".into()),
+                source: CredentialSource::Env("GITHUB_TOKEN".into()),
+            })
+        );
+        let discovered = discover(&[], "https://api.github.com").unwrap();
+        assert_eq!(discovered.credentials, Credentials::Token("gho_gh".into()));
+        assert_eq!(
+            discovered.source,
+            CredentialSource::GhConfig(home.join(".config/gh/hosts.yml"))
+        );
+        let discovered = discover(
+            &[("GITHUB_TOKEN", "ghp_env")],
+            "https://ghe.example.com/api/v3",
+        )
+        .unwrap();
+        assert_eq!(
+            discovered.credentials,
+            Credentials::Token("ghp_netrc".into())
+        );
+        assert_eq!(
+            discovered.source,
+            CredentialSource::Netrc(home.join(".netrc"))
+        );
+        assert_eq!(discover(&[], "https://other.example.com/api/v3"), None);
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
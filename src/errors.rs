@@ -1,5 +1,6 @@
 //! Client errors
 use crate::jwt::errors::Error as JWTError;
+use http::Error as HttpError;
 use http::StatusCode;
 use reqwest::Error as ReqwestError;
 use serde::Deserialize;
@@ -23,16 +24,41 @@ pub enum Error {
     },
     /// Error kind returned when a credential's rate limit has been exhausted. Wait for the reset duration before issuing more requests
     RateLimit { reset: Duration },
+    /// Error kind returned when GitHub's secondary (abuse) rate limits have been triggered,
+    /// typically by making too many concurrent or content creating requests. Wait for the
+    /// retry_after duration before issuing more requests
+    SecondaryRateLimit {
+        retry_after: Duration,
+        error: ClientError,
+    },
     /// Serialization related errors
     Codec(SerdeError),
     /// HTTP client errors
     Reqwest(ReqwestError),
+    /// Errors constructing HTTP requests or responses
+    Http(HttpError),
     /// Url format errors
     Url(ParseError),
     /// Network errors
     IO(IoError),
     /// JWT validation errors
     JWT(JWTError),
+    /// Errors reported by the GraphQL API in the `errors` member of a response
+    GraphQL(Vec<GraphQLError>),
+    /// Returned by requests made with conditions when the entity wasn't modified. See
+    /// [Github#conditional](../struct.Github.html#method.conditional), which yields
+    /// `Conditional::NotModified` instead. Also returned for `304` responses to requests
+    /// whose preconditions were set by a middleware or transport, unless the `httpcache`
+    /// feature is enabled and the cache holds the entity
+    NotModified,
+    /// Returned by [Github#with_response](../struct.Github.html#method.with_response) when
+    /// the call completed without making a request
+    NoResponse,
+    /// Returned for requests which can't be served from the cache while it is used in
+    /// `CacheMode::Offline`
+    Offline,
+    /// Errors reported by GitHub's OAuth endpoints while authorizing a user
+    OAuth(OAuthError),
 }
 
 impl From<SerdeError> for Error {
@@ -47,6 +73,12 @@ impl From<ReqwestError> for Error {
     }
 }
 
+impl From<HttpError> for Error {
+    fn from(err: HttpError) -> Self {
+        Error::Http(err)
+    }
+}
+
 impl From<ParseError> for Error {
     fn from(err: ParseError) -> Self {
         Error::Url(err)
@@ -70,6 +102,7 @@ impl StdError for Error {
         match self {
             Error::Codec(err) => Some(err),
             Error::Reqwest(err) => Some(err),
+            Error::Http(err) => Some(err),
             Error::Url(err) => Some(err),
             Error::IO(err) => Some(err),
             Error::JWT(err) => Some(err),
@@ -87,11 +120,32 @@ impl fmt::Display for Error {
                 "Rate limit exhausted. Will reset in {} seconds",
                 reset.as_secs()
             ),
+            Error::SecondaryRateLimit { retry_after, error } => write!(
+                f,
+                "{} Retry after {} seconds",
+                error.message,
+                retry_after.as_secs()
+            ),
             Error::Codec(err) => write!(f, "{}", err),
             Error::Reqwest(err) => write!(f, "{}", err),
+            Error::Http(err) => write!(f, "{}", err),
             Error::Url(err) => write!(f, "{}", err),
             Error::IO(err) => write!(f, "{}", err),
             Error::JWT(err) => write!(f, "{}", err),
+            Error::GraphQL(errors) => {
+                let messages = errors
+                    .iter()
+                    .map(|error| error.message.as_str())
+                    .collect::<Vec<_>>();
+                write!(f, "GraphQL errors: {}", messages.join("; "))
+            }
+            Error::NotModified => write!(f, "Not modified"),
+            Error::NoResponse => write!(f, "No request was made"),
+            Error::Offline => write!(f, "Not available offline"),
+            Error::OAuth(error) => match &error.error_description {
+                Some(description) => write!(f, "{}: {}", error.error, description),
+                None => write!(f, "{}", error.error),
+            },
         }
     }
 }
@@ -114,13 +168,43 @@ pub struct ClientError {
     pub documentation_url: Option<String>,
 }
 
+/// An error reported by the GraphQL API
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct GraphQLError {
+    pub message: String,
+    /// The kind of error, e.g. `NOT_FOUND`
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+    /// Path of the response field the error relates to
+    #[serde(default)]
+    pub path: Vec<serde_json::Value>,
+    /// Locations in the query the error relates to
+    #[serde(default)]
+    pub locations: Vec<GraphQLErrorLocation>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct GraphQLErrorLocation {
+    pub line: u64,
+    pub column: u64,
+}
+
+/// An error reported by GitHub's OAuth endpoints
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct OAuthError {
+    /// The kind of error, e.g. `bad_verification_code`
+    pub error: String,
+    pub error_description: Option<String>,
+    pub error_uri: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::{ClientError, FieldErr};
 
     #[test]
     fn deserialize_client_field_errors() {
-        for (json, expect) in vec![
+        for (json, expect) in [
             // see https://github.com/softprops/hubcaps/issues/31
             (
                 r#"{"message": "Validation Failed","errors":
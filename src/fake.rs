@@ -0,0 +1,1111 @@
+//! An in-process fake of the GitHub API for tests
+//!
+//! [FakeGithub](struct.FakeGithub.html) is a local HTTP server which keeps repositories,
+//! issues, pull requests, labels, comments and commit statuses in memory. It answers the same
+//! paths hubcaps' services request, including `Link` header pagination, so a client created
+//! with [Github#host](../struct.Github.html#method.host) can be exercised end to end with
+//! state which changes in response to its requests.
+//!
+//! Only the subset of the API listed below is implemented, anything else is answered with a
+//! `404`. Content created through the fake is attributed to the `octocat` user regardless of
+//! the credentials used.
+//!
+//! * `/repos/{owner}/{repo}`
+//! * `/repos/{owner}/{repo}/issues`, `/repos/{owner}/{repo}/issues/{number}` and the issue's
+//!   `labels` and `comments`
+//! * `/repos/{owner}/{repo}/pulls` and `/repos/{owner}/{repo}/pulls/{number}`
+//! * `/repos/{owner}/{repo}/labels` and `/repos/{owner}/{repo}/labels/{name}`
+//! * `/repos/{owner}/{repo}/statuses/{sha}` and `/repos/{owner}/{repo}/commits/{sha}/statuses`
+//!
+//! This module requires the `fake` feature.
+//!
+//! ```no_run
+//! use hubcaps::{fake::FakeGithub, issues::IssueOptions, Github};
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let fake = FakeGithub::start()?;
+//! fake.create_repo("octocat", "hello-world");
+//! let github = Github::host(fake.url(), "user-agent-name", None)?;
+//! let issue = github
+//!     .repo("octocat", "hello-world")
+//!     .issues()
+//!     .create(&IssueOptions::new("title", None::<String>, None::<String>, None, vec!["bug"]))
+//!     .await?;
+//! assert_eq!(issue.number, 1);
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::channel::oneshot;
+use futures::prelude::*;
+use http::header::{CONTENT_TYPE, LINK};
+use http::{Method, Response, StatusCode, Uri};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Server};
+use log::debug;
+use percent_encoding::percent_decode_str;
+use serde_json::{Map, Value};
+use url::form_urlencoded;
+
+use crate::{Error, Result, Timestamp};
+
+/// The login of the user all content is attributed to
+const LOGIN: &str = "octocat";
+
+const DEFAULT_PER_PAGE: usize = 30;
+const MAX_PER_PAGE: usize = 100;
+
+/// A fake GitHub API server listening on a local port
+///
+/// The server is shut down when this value is dropped.
+#[derive(Debug)]
+pub struct FakeGithub {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl FakeGithub {
+    /// Start a server on a random local port
+    ///
+    /// The server runs as a task on the current tokio runtime, so this must be called from
+    /// within one.
+    pub fn start() -> Result<Self> {
+        let server = Server::try_bind(&([127, 0, 0, 1], 0).into())
+            .map_err(|err| Error::IO(io::Error::other(err)))?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let service_state = state.clone();
+        let server = server.serve(make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| serve(state.clone(), req))) }
+        }));
+        let addr = server.local_addr();
+        state.lock().unwrap().base = format!("http://{}", addr);
+
+        let (shutdown, signal) = oneshot::channel::<()>();
+        tokio::spawn(
+            server
+                .with_graceful_shutdown(signal.map(|_| ()))
+                .map(|result| {
+                    if let Err(err) = result {
+                        debug!("fake github server failed: {}", err);
+                    }
+                }),
+        );
+        Ok(FakeGithub {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// The base url of the server, to be passed to `Github::host`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Create an empty repository. Creating a repository which already exists has no effect
+    pub fn create_repo<O, R>(&self, owner: O, repo: R) -> &Self
+    where
+        O: Into<String>,
+        R: Into<String>,
+    {
+        let mut state = self.state.lock().unwrap();
+        let key = (owner.into(), repo.into());
+        if !state.repos.contains_key(&key) {
+            let id = state.next_id();
+            let repo = RepoRecord::new(id, &key.0, &key.1);
+            state.repos.insert(key, repo);
+        }
+        self
+    }
+}
+
+impl Drop for FakeGithub {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn serve(
+    state: Arc<Mutex<State>>,
+    request: hyper::Request<Body>,
+) -> std::result::Result<hyper::Response<Body>, Infallible> {
+    let (parts, body) = request.into_parts();
+    let response = match hyper::body::to_bytes(body).await {
+        Ok(body) => state
+            .lock()
+            .unwrap()
+            .handle(&parts.method, &parts.uri, &body),
+        Err(_) => message(StatusCode::BAD_REQUEST, "Problems reading body"),
+    };
+    debug!("{} {} -> {}", parts.method, parts.uri, response.status());
+    Ok(response.map(Body::from))
+}
+
+#[derive(Debug, Default)]
+struct State {
+    base: String,
+    last_id: u64,
+    repos: BTreeMap<(String, String), RepoRecord>,
+}
+
+#[derive(Debug)]
+struct RepoRecord {
+    id: u64,
+    owner: String,
+    name: String,
+    created_at: String,
+    last_number: u64,
+    labels: Vec<LabelRecord>,
+    issues: BTreeMap<u64, IssueRecord>,
+    comments: Vec<CommentRecord>,
+    statuses: Vec<StatusRecord>,
+}
+
+#[derive(Debug)]
+struct LabelRecord {
+    name: String,
+    color: String,
+    description: Option<String>,
+}
+
+/// An issue, or a pull request when `pull` is set. Both share the same numbers
+#[derive(Debug)]
+struct IssueRecord {
+    id: u64,
+    number: u64,
+    title: String,
+    body: Option<String>,
+    state: String,
+    labels: Vec<String>,
+    assignees: Vec<String>,
+    pull: Option<PullRecord>,
+    created_at: String,
+    updated_at: String,
+    closed_at: Option<String>,
+}
+
+#[derive(Debug)]
+struct PullRecord {
+    head: String,
+    base: String,
+}
+
+#[derive(Debug)]
+struct CommentRecord {
+    id: u64,
+    issue: u64,
+    body: String,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Debug)]
+struct StatusRecord {
+    id: u64,
+    sha: String,
+    state: String,
+    target_url: Option<String>,
+    description: Option<String>,
+    context: String,
+    created_at: String,
+}
+
+impl State {
+    fn next_id(&mut self) -> u64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn handle(&mut self, method: &Method, uri: &Uri, body: &[u8]) -> Response<Vec<u8>> {
+        let segments = uri
+            .path()
+            .trim_matches('/')
+            .split('/')
+            .map(|segment| percent_decode_str(segment).decode_utf8_lossy().into_owned())
+            .collect::<Vec<_>>();
+        let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+        let body = if body.is_empty() {
+            Value::Null
+        } else {
+            match serde_json::from_slice(body) {
+                Ok(body) => body,
+                Err(_) => return message(StatusCode::BAD_REQUEST, "Problems parsing JSON"),
+            }
+        };
+        match segments.as_slice() {
+            ["repos", owner, repo, rest @ ..] => {
+                let key = ((*owner).to_owned(), (*repo).to_owned());
+                let id = self.next_id();
+                match self.repos.get_mut(&key) {
+                    Some(record) => {
+                        let request = RepoRequest {
+                            base: &self.base,
+                            uri,
+                            id,
+                            body: &body,
+                        };
+                        record.handle(&request, method, rest)
+                    }
+                    None => not_found(),
+                }
+            }
+            _ => not_found(),
+        }
+    }
+}
+
+/// A request for a resource within a repository
+struct RepoRequest<'a> {
+    base: &'a str,
+    uri: &'a Uri,
+    /// An id available for resources created by this request
+    id: u64,
+    body: &'a Value,
+}
+
+impl RepoRequest<'_> {
+    fn str(&self, field: &str) -> Option<String> {
+        self.body
+            .get(field)
+            .and_then(Value::as_str)
+            .map(str::to_owned)
+    }
+
+    fn strs(&self, field: &str) -> Option<Vec<String>> {
+        self.body.get(field).and_then(strings)
+    }
+
+    fn query(&self, name: &str) -> Option<String> {
+        form_urlencoded::parse(self.uri.query().unwrap_or("").as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    }
+}
+
+impl RepoRecord {
+    fn new(id: u64, owner: &str, name: &str) -> Self {
+        RepoRecord {
+            id,
+            owner: owner.into(),
+            name: name.into(),
+            created_at: timestamp(),
+            last_number: 0,
+            labels: Vec::new(),
+            issues: BTreeMap::new(),
+            comments: Vec::new(),
+            statuses: Vec::new(),
+        }
+    }
+
+    fn url(&self, base: &str) -> String {
+        format!("{}/repos/{}/{}", base, self.owner, self.name)
+    }
+
+    fn handle(&mut self, req: &RepoRequest, method: &Method, path: &[&str]) -> Response<Vec<u8>> {
+        match (method, path) {
+            (&Method::GET, []) => ok(self.render(req.base)),
+            (&Method::GET, ["issues"]) => self.list_issues(req, false),
+            (&Method::POST, ["issues"]) => self.create_issue(req, None),
+            (&Method::GET, ["pulls"]) => self.list_issues(req, true),
+            (&Method::POST, ["pulls"]) => match (req.str("head"), req.str("base")) {
+                (Some(head), Some(base)) => self.create_issue(req, Some(PullRecord { head, base })),
+                _ => invalid("PullRequest", "head"),
+            },
+            (_, ["issues", number, rest @ ..]) | (_, ["pulls", number, rest @ ..]) => {
+                let pull = path[0] == "pulls";
+                match number.parse::<u64>() {
+                    Ok(number)
+                        if self
+                            .issues
+                            .get(&number)
+                            .is_some_and(|issue| !pull || issue.pull.is_some()) =>
+                    {
+                        self.handle_issue(req, method, number, pull, rest)
+                    }
+                    _ => not_found(),
+                }
+            }
+            (&Method::GET, ["labels"]) => paginate(
+                req,
+                self.labels
+                    .iter()
+                    .map(|label| self.render_label(req.base, label))
+                    .collect(),
+            ),
+            (&Method::POST, ["labels"]) => match req.str("name") {
+                Some(name) if self.label(&name).is_some() => already_exists("Label", "name"),
+                Some(name) => {
+                    self.labels.push(LabelRecord {
+                        name,
+                        color: req.str("color").unwrap_or_else(|| "ededed".into()),
+                        description: req.str("description"),
+                    });
+                    created(self.render_label(req.base, &self.labels[self.labels.len() - 1]))
+                }
+                None => invalid("Label", "name"),
+            },
+            (_, ["labels", name]) => self.handle_label(req, method, name),
+            (&Method::POST, ["statuses", sha]) => match req.str("state") {
+                Some(state) => {
+                    self.statuses.push(StatusRecord {
+                        id: req.id,
+                        sha: (*sha).to_owned(),
+                        state,
+                        target_url: req.str("target_url"),
+                        description: req.str("description"),
+                        context: req.str("context").unwrap_or_else(|| "default".into()),
+                        created_at: timestamp(),
+                    });
+                    created(self.render_status(req.base, &self.statuses[self.statuses.len() - 1]))
+                }
+                None => invalid("Status", "state"),
+            },
+            (&Method::GET, ["commits", sha, "statuses"]) => paginate(
+                req,
+                self.statuses
+                    .iter()
+                    .rev()
+                    .filter(|status| status.sha == *sha)
+                    .map(|status| self.render_status(req.base, status))
+                    .collect(),
+            ),
+            _ => not_found(),
+        }
+    }
+
+    fn list_issues(&self, req: &RepoRequest, pulls: bool) -> Response<Vec<u8>> {
+        let state = req.query("state").unwrap_or_else(|| "open".into());
+        let labels = req
+            .query("labels")
+            .map(|labels| {
+                labels
+                    .split(',')
+                    .filter(|label| !label.is_empty())
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let mut issues = self
+            .issues
+            .values()
+            .filter(|issue| !pulls || issue.pull.is_some())
+            .filter(|issue| state == "all" || issue.state == state)
+            .filter(|issue| labels.iter().all(|label| issue.labels.contains(label)))
+            .collect::<Vec<_>>();
+        if req.query("direction").as_deref() != Some("asc") {
+            issues.reverse();
+        }
+        paginate(
+            req,
+            issues
+                .into_iter()
+                .map(|issue| {
+                    if pulls {
+                        self.render_pull(req.base, issue)
+                    } else {
+                        self.render_issue(req.base, issue)
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    fn create_issue(&mut self, req: &RepoRequest, pull: Option<PullRecord>) -> Response<Vec<u8>> {
+        let title = match req.str("title") {
+            Some(title) => title,
+            None if pull.is_some() => return invalid("PullRequest", "title"),
+            None => return invalid("Issue", "title"),
+        };
+        let labels = req.strs("labels").unwrap_or_default();
+        self.ensure_labels(&labels);
+        let now = timestamp();
+        self.last_number += 1;
+        let issue = IssueRecord {
+            id: req.id,
+            number: self.last_number,
+            title,
+            body: req.str("body"),
+            state: "open".into(),
+            labels,
+            assignees: assignees(req),
+            pull,
+            created_at: now.clone(),
+            updated_at: now,
+            closed_at: None,
+        };
+        let rendered = if issue.pull.is_some() {
+            self.render_pull(req.base, &issue)
+        } else {
+            self.render_issue(req.base, &issue)
+        };
+        self.issues.insert(issue.number, issue);
+        created(rendered)
+    }
+
+    fn handle_issue(
+        &mut self,
+        req: &RepoRequest,
+        method: &Method,
+        number: u64,
+        pull: bool,
+        path: &[&str],
+    ) -> Response<Vec<u8>> {
+        match (method, path) {
+            (&Method::GET, []) => {
+                let issue = &self.issues[&number];
+                if pull {
+                    ok(self.render_pull(req.base, issue))
+                } else {
+                    ok(self.render_issue(req.base, issue))
+                }
+            }
+            (&Method::PATCH, []) => {
+                let labels = req.strs("labels");
+                if let Some(labels) = &labels {
+                    self.ensure_labels(labels);
+                }
+                let issue = self.issues.get_mut(&number).unwrap();
+                if let Some(title) = req.str("title") {
+                    issue.title = title;
+                }
+                if req.body.get("body").is_some() {
+                    issue.body = req.str("body");
+                }
+                if let Some(labels) = labels {
+                    issue.labels = labels;
+                }
+                if req.body.get("assignee").is_some() || req.body.get("assignees").is_some() {
+                    issue.assignees = assignees(req);
+                }
+                let now = timestamp();
+                match req.str("state").as_deref() {
+                    Some("closed") if issue.state != "closed" => {
+                        issue.state = "closed".into();
+                        issue.closed_at = Some(now.clone());
+                    }
+                    Some("open") => {
+                        issue.state = "open".into();
+                        issue.closed_at = None;
+                    }
+                    _ => (),
+                }
+                issue.updated_at = now;
+                let issue = &self.issues[&number];
+                if pull {
+                    ok(self.render_pull(req.base, issue))
+                } else {
+                    ok(self.render_issue(req.base, issue))
+                }
+            }
+            (&Method::GET, ["labels"]) => self.issue_labels(req, number, StatusCode::OK),
+            (&Method::POST, ["labels"]) | (&Method::PUT, ["labels"]) => {
+                // labels are sent either as a plain array or as `{"labels": [..]}`
+                let labels = match strings(req.body).or_else(|| req.strs("labels")) {
+                    Some(labels) => labels,
+                    None => return invalid("Label", "name"),
+                };
+                self.ensure_labels(&labels);
+                let issue = self.issues.get_mut(&number).unwrap();
+                if method == Method::PUT {
+                    issue.labels.clear();
+                }
+                for label in labels {
+                    if !issue.labels.contains(&label) {
+                        issue.labels.push(label);
+                    }
+                }
+                issue.updated_at = timestamp();
+                self.issue_labels(req, number, StatusCode::OK)
+            }
+            (&Method::DELETE, ["labels"]) => {
+                let issue = self.issues.get_mut(&number).unwrap();
+                issue.labels.clear();
+                issue.updated_at = timestamp();
+                no_content()
+            }
+            (&Method::DELETE, ["labels", name]) => {
+                let issue = self.issues.get_mut(&number).unwrap();
+                match issue.labels.iter().position(|label| label == name) {
+                    Some(index) => {
+                        issue.labels.remove(index);
+                        issue.updated_at = timestamp();
+                        self.issue_labels(req, number, StatusCode::OK)
+                    }
+                    None => message(StatusCode::NOT_FOUND, "Label does not exist"),
+                }
+            }
+            (&Method::GET, ["comments"]) => paginate(
+                req,
+                self.comments
+                    .iter()
+                    .filter(|comment| comment.issue == number)
+                    .map(|comment| self.render_comment(req.base, comment))
+                    .collect(),
+            ),
+            (&Method::POST, ["comments"]) => match req.str("body") {
+                Some(body) => {
+                    let now = timestamp();
+                    self.comments.push(CommentRecord {
+                        id: req.id,
+                        issue: number,
+                        body,
+                        created_at: now.clone(),
+                        updated_at: now,
+                    });
+                    let comment = &self.comments[self.comments.len() - 1];
+                    created(self.render_comment(req.base, comment))
+                }
+                None => invalid("IssueComment", "body"),
+            },
+            _ => not_found(),
+        }
+    }
+
+    fn handle_label(
+        &mut self,
+        req: &RepoRequest,
+        method: &Method,
+        name: &str,
+    ) -> Response<Vec<u8>> {
+        let index = match self.labels.iter().position(|label| label.name == name) {
+            Some(index) => index,
+            None => return not_found(),
+        };
+        match *method {
+            Method::GET => ok(self.render_label(req.base, &self.labels[index])),
+            Method::PATCH => {
+                let renamed = req
+                    .str("new_name")
+                    .or_else(|| req.str("name"))
+                    .filter(|renamed| renamed != name);
+                if let Some(renamed) = renamed {
+                    if self.label(&renamed).is_some() {
+                        return already_exists("Label", "name");
+                    }
+                    for issue in self.issues.values_mut() {
+                        for label in issue.labels.iter_mut().filter(|label| *label == name) {
+                            *label = renamed.clone();
+                        }
+                    }
+                    self.labels[index].name = renamed;
+                }
+                let label = &mut self.labels[index];
+                if let Some(color) = req.str("color") {
+                    label.color = color;
+                }
+                if req.body.get("description").is_some() {
+                    label.description = req.str("description");
+                }
+                ok(self.render_label(req.base, &self.labels[index]))
+            }
+            Method::DELETE => {
+                self.labels.remove(index);
+                for issue in self.issues.values_mut() {
+                    issue.labels.retain(|label| label != name);
+                }
+                no_content()
+            }
+            _ => not_found(),
+        }
+    }
+
+    fn issue_labels(
+        &self,
+        req: &RepoRequest,
+        number: u64,
+        status: StatusCode,
+    ) -> Response<Vec<u8>> {
+        let labels = self.issues[&number]
+            .labels
+            .iter()
+            .filter_map(|name| self.label(name))
+            .map(|label| self.render_label(req.base, label))
+            .collect::<Vec<_>>();
+        response(status, &Value::Array(labels))
+    }
+
+    fn label(&self, name: &str) -> Option<&LabelRecord> {
+        self.labels.iter().find(|label| label.name == name)
+    }
+
+    /// Like GitHub, create labels applied to issues which don't exist yet
+    fn ensure_labels(&mut self, names: &[String]) {
+        for name in names {
+            if self.label(name).is_none() {
+                self.labels.push(LabelRecord {
+                    name: name.clone(),
+                    color: "ededed".into(),
+                    description: None,
+                });
+            }
+        }
+    }
+
+    fn render(&self, base: &str) -> Value {
+        let url = self.url(base);
+        let html_url = format!("{}/{}/{}", base, self.owner, self.name);
+        let mut repo = Map::new();
+        for (name, path) in &[
+            ("archive_url", "/{archive_format}{/ref}"),
+            ("assignees_url", "/assignees{/user}"),
+            ("blobs_url", "/git/blobs{/sha}"),
+            ("branches_url", "/branches{/branch}"),
+            ("collaborators_url", "/collaborators{/collaborator}"),
+            ("comments_url", "/comments{/number}"),
+            ("commits_url", "/commits{/sha}"),
+            ("compare_url", "/compare/{base}...{head}"),
+            ("contents_url", "/contents/{+path}"),
+            ("contributors_url", "/contributors"),
+            ("deployments_url", "/deployments"),
+            ("downloads_url", "/downloads"),
+            ("events_url", "/events"),
+            ("forks_url", "/forks"),
+            ("git_commits_url", "/git/commits{/sha}"),
+            ("git_refs_url", "/git/refs{/sha}"),
+            ("git_tags_url", "/git/tags{/sha}"),
+            ("hooks_url", "/hooks"),
+            ("issue_comment_url", "/issues/comments{/number}"),
+            ("issue_events_url", "/issues/events{/number}"),
+            ("issues_url", "/issues{/number}"),
+            ("keys_url", "/keys{/key_id}"),
+            ("labels_url", "/labels{/name}"),
+            ("languages_url", "/languages"),
+            ("merges_url", "/merges"),
+            ("milestones_url", "/milestones{/number}"),
+            (
+                "notifications_url",
+                "/notifications{?since,all,participating}",
+            ),
+            ("pulls_url", "/pulls{/number}"),
+            ("releases_url", "/releases{/id}"),
+            ("stargazers_url", "/stargazers"),
+            ("statuses_url", "/statuses/{sha}"),
+            ("subscribers_url", "/subscribers"),
+            ("subscription_url", "/subscription"),
+            ("tags_url", "/tags"),
+            ("teams_url", "/teams"),
+            ("trees_url", "/git/trees{/sha}"),
+        ] {
+            repo.insert((*name).into(), format!("{}{}", url, path).into());
+        }
+        let open_issues = self
+            .issues
+            .values()
+            .filter(|issue| issue.state == "open")
+            .count();
+        let fields = serde_json::json!({
+            "id": self.id,
+            "owner": user(base, &self.owner),
+            "name": self.name,
+            "full_name": format!("{}/{}", self.owner, self.name),
+            "description": null,
+            "private": false,
+            "fork": false,
+            "url": url,
+            "html_url": html_url,
+            "clone_url": format!("{}.git", html_url),
+            "git_url": format!("{}.git", html_url),
+            "ssh_url": format!("{}.git", html_url),
+            "svn_url": html_url,
+            "mirror_url": null,
+            "homepage": null,
+            "language": null,
+            "forks_count": 0,
+            "stargazers_count": 0,
+            "watchers_count": 0,
+            "size": 0,
+            "default_branch": "master",
+            "open_issues_count": open_issues,
+            "has_issues": true,
+            "has_wiki": false,
+            "has_pages": false,
+            "has_downloads": false,
+            "archived": false,
+            "pushed_at": self.created_at,
+            "created_at": self.created_at,
+            "updated_at": self.created_at,
+        });
+        if let Value::Object(fields) = fields {
+            repo.extend(fields);
+        }
+        Value::Object(repo)
+    }
+
+    fn render_label(&self, base: &str, label: &LabelRecord) -> Value {
+        serde_json::json!({
+            "url": format!("{}/labels/{}", self.url(base), label.name),
+            "name": label.name,
+            "color": label.color,
+            "description": label.description,
+        })
+    }
+
+    fn render_issue(&self, base: &str, issue: &IssueRecord) -> Value {
+        let url = format!("{}/issues/{}", self.url(base), issue.number);
+        let html_url = format!(
+            "{}/{}/{}/issues/{}",
+            base, self.owner, self.name, issue.number
+        );
+        let pull_request = issue.pull.as_ref().map(|_| {
+            let html_url = format!(
+                "{}/{}/{}/pull/{}",
+                base, self.owner, self.name, issue.number
+            );
+            serde_json::json!({
+                "url": format!("{}/pulls/{}", self.url(base), issue.number),
+                "html_url": html_url,
+                "diff_url": format!("{}.diff", html_url),
+                "patch_url": format!("{}.patch", html_url),
+            })
+        });
+        serde_json::json!({
+            "id": issue.id,
+            "url": url,
+            "labels_url": format!("{}/labels{{/name}}", url),
+            "comments_url": format!("{}/comments", url),
+            "events_url": format!("{}/events", url),
+            "html_url": html_url,
+            "number": issue.number,
+            "state": issue.state,
+            "title": issue.title,
+            "body": issue.body,
+            "user": user(base, LOGIN),
+            "labels": self.render_labels(base, issue),
+            "assignee": issue.assignees.first().map(|login| user(base, login)),
+            "assignees": issue.assignees.iter().map(|login| user(base, login)).collect::<Vec<_>>(),
+            "locked": false,
+            "comments": self.comments.iter().filter(|comment| comment.issue == issue.number).count(),
+            "pull_request": pull_request,
+            "closed_at": issue.closed_at,
+            "created_at": issue.created_at,
+            "updated_at": issue.updated_at,
+        })
+    }
+
+    fn render_pull(&self, base: &str, issue: &IssueRecord) -> Value {
+        let pull = issue.pull.as_ref().expect("pull request");
+        let repo_url = self.url(base);
+        let url = format!("{}/pulls/{}", repo_url, issue.number);
+        let issue_url = format!("{}/issues/{}", repo_url, issue.number);
+        let html_url = format!(
+            "{}/{}/{}/pull/{}",
+            base, self.owner, self.name, issue.number
+        );
+        let head = self.render_commit(base, &pull.head);
+        serde_json::json!({
+            "id": issue.id,
+            "url": url,
+            "html_url": html_url,
+            "diff_url": format!("{}.diff", html_url),
+            "patch_url": format!("{}.patch", html_url),
+            "issue_url": issue_url,
+            "commits_url": format!("{}/commits", url),
+            "review_comments_url": format!("{}/comments", url),
+            "review_comment_url": format!("{}/pulls/comments{{/number}}", repo_url),
+            "comments_url": format!("{}/comments", issue_url),
+            "statuses_url": format!("{}/statuses/{}", repo_url, head["sha"].as_str().unwrap_or("")),
+            "number": issue.number,
+            "state": issue.state,
+            "title": issue.title,
+            "body": issue.body,
+            "created_at": issue.created_at,
+            "updated_at": issue.updated_at,
+            "closed_at": issue.closed_at,
+            "merged_at": null,
+            "head": head,
+            "base": self.render_commit(base, &pull.base),
+            "user": user(base, LOGIN),
+            "assignee": issue.assignees.first().map(|login| user(base, login)),
+            "assignees": issue.assignees.iter().map(|login| user(base, login)).collect::<Vec<_>>(),
+            "merge_commit_sha": null,
+            "merged": false,
+            "mergeable": null,
+            "merged_by": null,
+            "comments": self.comments.iter().filter(|comment| comment.issue == issue.number).count(),
+            "commits": null,
+            "additions": null,
+            "deletions": null,
+            "changed_files": null,
+            "labels": self.render_labels(base, issue),
+        })
+    }
+
+    fn render_labels(&self, base: &str, issue: &IssueRecord) -> Vec<Value> {
+        issue
+            .labels
+            .iter()
+            .filter_map(|name| self.label(name))
+            .map(|label| self.render_label(base, label))
+            .collect()
+    }
+
+    /// Renders a branch of this repository, which may be given as `owner:branch`
+    fn render_commit(&self, base: &str, branch: &str) -> Value {
+        let (owner, branch) = match branch.find(':') {
+            Some(index) => (&branch[..index], &branch[index + 1..]),
+            None => (self.owner.as_str(), branch),
+        };
+        serde_json::json!({
+            "label": format!("{}:{}", owner, branch),
+            "ref": branch,
+            "sha": sha(&format!("{}/{}:{}", owner, self.name, branch)),
+            "user": user(base, owner),
+        })
+    }
+
+    fn render_comment(&self, base: &str, comment: &CommentRecord) -> Value {
+        serde_json::json!({
+            "id": comment.id,
+            "url": format!("{}/issues/comments/{}", self.url(base), comment.id),
+            "html_url": format!(
+                "{}/{}/{}/issues/{}#issuecomment-{}",
+                base, self.owner, self.name, comment.issue, comment.id
+            ),
+            "body": comment.body,
+            "user": user(base, LOGIN),
+            "created_at": comment.created_at,
+            "updated_at": comment.updated_at,
+        })
+    }
+
+    fn render_status(&self, base: &str, status: &StatusRecord) -> Value {
+        serde_json::json!({
+            "id": status.id,
+            "url": format!("{}/statuses/{}", self.url(base), status.sha),
+            "state": status.state,
+            "target_url": status.target_url,
+            "description": status.description.clone().unwrap_or_default(),
+            "context": status.context,
+            "creator": user(base, LOGIN),
+            "created_at": status.created_at,
+            "updated_at": status.created_at,
+        })
+    }
+}
+
+fn user(base: &str, login: &str) -> Value {
+    let url = format!("{}/users/{}", base, login);
+    serde_json::json!({
+        "login": login,
+        "id": sha(login)[..8].chars().fold(0u64, |id, c| id * 16 + c.to_digit(16).unwrap_or(0) as u64),
+        "avatar_url": format!("{}/avatars/{}", base, login),
+        "gravatar_id": "",
+        "url": url,
+        "html_url": format!("{}/{}", base, login),
+        "followers_url": format!("{}/followers", url),
+        "following_url": format!("{}/following{{/other_user}}", url),
+        "gists_url": format!("{}/gists{{/gist_id}}", url),
+        "starred_url": format!("{}/starred{{/owner}}{{/repo}}", url),
+        "subscriptions_url": format!("{}/subscriptions", url),
+        "organizations_url": format!("{}/orgs", url),
+        "repos_url": format!("{}/repos", url),
+        "events_url": format!("{}/events{{/privacy}}", url),
+        "received_events_url": format!("{}/received_events", url),
+        "site_admin": false,
+    })
+}
+
+/// A stable, sha-like hex string for the given input
+fn sha(input: &str) -> String {
+    (0..3)
+        .map(|round| {
+            let mut hasher = DefaultHasher::new();
+            (round, input).hash(&mut hasher);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect::<String>()[..40]
+        .to_owned()
+}
+
+fn strings(value: &Value) -> Option<Vec<String>> {
+    value.as_array().map(|values| {
+        values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_owned)
+            .collect()
+    })
+}
+
+fn assignees(req: &RepoRequest) -> Vec<String> {
+    req.strs("assignees")
+        .or_else(|| req.str("assignee").map(|assignee| vec![assignee]))
+        .unwrap_or_default()
+}
+
+/// Respond with one page of items, linking to the other pages like GitHub does
+fn paginate(req: &RepoRequest, items: Vec<Value>) -> Response<Vec<u8>> {
+    let params = form_urlencoded::parse(req.uri.query().unwrap_or("").as_bytes())
+        .into_owned()
+        .filter(|(key, _)| key != "page")
+        .collect::<Vec<_>>();
+    let page = req
+        .query("page")
+        .and_then(|page| page.parse::<usize>().ok())
+        .unwrap_or(1)
+        .max(1);
+    let per_page = req
+        .query("per_page")
+        .and_then(|per_page| per_page.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_PER_PAGE)
+        .clamp(1, MAX_PER_PAGE);
+    let last = items.len().div_ceil(per_page).max(1);
+    let link = |page: usize, rel: &str| {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&params)
+            .append_pair("page", &page.to_string())
+            .finish();
+        format!(
+            "<{}{}?{}>; rel=\"{}\"",
+            req.base,
+            req.uri.path(),
+            query,
+            rel
+        )
+    };
+    let mut links = Vec::new();
+    if page < last {
+        links.push(link(page + 1, "next"));
+        links.push(link(last, "last"));
+    }
+    if page > 1 {
+        links.push(link(1, "first"));
+        links.push(link(page - 1, "prev"));
+    }
+    let items = items
+        .into_iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .collect();
+    let mut response = ok(Value::Array(items));
+    if !links.is_empty() {
+        if let Ok(value) = links.join(", ").parse() {
+            response.headers_mut().insert(LINK, value);
+        }
+    }
+    response
+}
+
+fn response(status: StatusCode, body: &Value) -> Response<Vec<u8>> {
+    let mut response = Response::new(serde_json::to_vec(body).unwrap_or_default());
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        "application/json; charset=utf-8".parse().unwrap(),
+    );
+    response
+}
+
+fn ok(body: Value) -> Response<Vec<u8>> {
+    response(StatusCode::OK, &body)
+}
+
+fn created(body: Value) -> Response<Vec<u8>> {
+    response(StatusCode::CREATED, &body)
+}
+
+fn no_content() -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = StatusCode::NO_CONTENT;
+    response
+}
+
+fn message(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    response(
+        status,
+        &serde_json::json!({
+            "message": message,
+            "documentation_url": "https://developer.github.com/v3",
+        }),
+    )
+}
+
+fn not_found() -> Response<Vec<u8>> {
+    message(StatusCode::NOT_FOUND, "Not Found")
+}
+
+fn validation_failed(resource: &str, field: &str, code: &str) -> Response<Vec<u8>> {
+    response(
+        StatusCode::UNPROCESSABLE_ENTITY,
+        &serde_json::json!({
+            "message": "Validation Failed",
+            "errors": [{ "resource": resource, "field": field, "code": code }],
+            "documentation_url": "https://developer.github.com/v3",
+        }),
+    )
+}
+
+fn invalid(resource: &str, field: &str) -> Response<Vec<u8>> {
+    validation_failed(resource, field, "missing_field")
+}
+
+fn already_exists(resource: &str, field: &str) -> Response<Vec<u8>> {
+    validation_failed(resource, field, "already_exists")
+}
+
+fn timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default();
+    format_timestamp(secs)
+}
+
+/// Format seconds since the unix epoch as an ISO 8601 UTC timestamp
+fn format_timestamp(secs: u64) -> String {
+    Timestamp::from(UNIX_EPOCH + Duration::from_secs(secs)).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(1_296_068_472), "2011-01-26T19:01:12Z");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn paginates_with_link_headers() {
+        let mut state = State {
+            base: "http://fake".into(),
+            ..State::default()
+        };
+        state
+            .repos
+            .insert(("o".into(), "r".into()), RepoRecord::new(1, "o", "r"));
+        for name in &["a", "b", "c"] {
+            let body = serde_json::to_vec(&serde_json::json!({ "name": name })).unwrap();
+            let uri = "/repos/o/r/labels".parse().unwrap();
+            assert_eq!(
+                state.handle(&Method::POST, &uri, &body).status(),
+                StatusCode::CREATED
+            );
+        }
+        let uri = "/repos/o/r/labels?per_page=1&page=2".parse().unwrap();
+        let response = state.handle(&Method::GET, &uri, &[]);
+        assert_eq!(
+            response.headers()[LINK],
+            "<http://fake/repos/o/r/labels?per_page=1&page=3>; rel=\"next\", \
+             <http://fake/repos/o/r/labels?per_page=1&page=3>; rel=\"last\", \
+             <http://fake/repos/o/r/labels?per_page=1&page=1>; rel=\"first\", \
+             <http://fake/repos/o/r/labels?per_page=1&page=1>; rel=\"prev\""
+        );
+        let labels: Vec<Value> = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(labels[0]["name"], "b");
+    }
+}
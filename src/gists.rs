@@ -1,6 +1,6 @@
 //! Gists interface
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Timestamp};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::hash::Hash;
@@ -113,10 +113,10 @@ pub struct GistListOptions {
 impl GistListOptions {
     pub fn since<T>(timestamp: T) -> GistListOptions
     where
-        T: Into<String>,
+        T: Into<Timestamp>,
     {
         let mut params = HashMap::new();
-        params.insert("since", timestamp.into());
+        params.insert("since", timestamp.into().to_string());
         GistListOptions { params }
     }
 
@@ -161,8 +161,8 @@ pub struct Gist {
     pub html_url: String,
     pub git_pull_url: String,
     pub git_push_url: String,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
 }
 
 #[derive(Debug, Deserialize)]
@@ -170,8 +170,8 @@ pub struct GistFork {
     pub user: User,
     pub url: String,
     pub id: String,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -0,0 +1,200 @@
+//! GraphQL (v4) API interface
+//!
+//! Queries are made with [Github#graphql](../struct.Github.html#method.graphql), which
+//! deserializes the `data` of a response into a caller provided type and reports the
+//! `errors` of a response as [Error::GraphQL](../errors/enum.Error.html#variant.GraphQL).
+//! [Github#graphql_stream](../struct.Github.html#method.graphql_stream) walks the pages of a
+//! connection, passing the end cursor of each page as the `$after` variable of the next query.
+//!
+//! ```no_run
+//! use futures::prelude::*;
+//! use hubcaps::graphql::Connection;
+//! use hubcaps::{Credentials, Github};
+//! use serde::Deserialize;
+//! use serde_json::json;
+//!
+//! #[derive(Deserialize)]
+//! struct Data {
+//!     repository: Repository,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Repository {
+//!     discussions: Connection<Discussion>,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Discussion {
+//!     title: String,
+//! }
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let github = Github::new(
+//!     "user-agent-name",
+//!     Credentials::Token("personal-access-token".into()),
+//! )?;
+//! let titles = github
+//!     .graphql_stream(
+//!         "query($owner: String!, $name: String!, $after: String) {
+//!             repository(owner: $owner, name: $name) {
+//!                 discussions(first: 100, after: $after) {
+//!                     nodes { title }
+//!                     pageInfo { endCursor hasNextPage }
+//!                 }
+//!             }
+//!         }",
+//!         json!({ "owner": "rust-lang", "name": "rust" }),
+//!         |data: Data| data.repository.discussions,
+//!     )
+//!     .map_ok(|discussion| discussion.title)
+//!     .try_collect::<Vec<_>>()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use serde::{Deserialize, Serialize};
+
+use crate::errors::GraphQLError;
+use crate::{Error, Result};
+
+/// A page of a connection, as selected with `nodes { .. } pageInfo { endCursor hasNextPage }`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection<T> {
+    pub nodes: Vec<T>,
+    pub page_info: PageInfo,
+    /// Only present if `totalCount` was selected
+    pub total_count: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub end_cursor: Option<String>,
+    pub has_next_page: bool,
+}
+
+impl PageInfo {
+    /// The cursor to request the next page with, if there is one
+    pub fn next_cursor(&self) -> Option<&str> {
+        if self.has_next_page {
+            self.end_cursor.as_deref()
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Query<'a, V> {
+    pub query: &'a str,
+    pub variables: V,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Response<D> {
+    data: Option<D>,
+    #[serde(default)]
+    errors: Vec<GraphQLError>,
+}
+
+impl<D> Response<D>
+where
+    D: serde::de::DeserializeOwned,
+{
+    /// The data of a response without errors
+    pub fn into_result(self) -> Result<D> {
+        if !self.errors.is_empty() {
+            return Err(Error::GraphQL(self.errors));
+        }
+        match self.data {
+            Some(data) => Ok(data),
+            None => Ok(serde_json::from_value(serde_json::Value::Null)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{github, FakeTransport};
+    use crate::Github;
+    use futures::prelude::*;
+    use http::Method;
+
+    #[tokio::test]
+    async fn graphql_queries() {
+        let transport = FakeTransport::new(vec![
+            (200, r#"{"data":{"viewer":{"login":"octocat"}}}"#),
+            (
+                200,
+                r#"{"data":null,"errors":[{"type":"NOT_FOUND","path":["repository"],"message":"Could not resolve to a Repository"}]}"#,
+            ),
+        ]);
+        let github = github(&transport);
+        let data: serde_json::Value = github
+            .graphql("query { viewer { login } }", ())
+            .await
+            .unwrap();
+        assert_eq!(data["viewer"]["login"], "octocat");
+        match github
+            .graphql::<_, serde_json::Value>("query { repository }", ())
+            .await
+        {
+            Err(Error::GraphQL(errors)) => {
+                assert_eq!(errors[0].error_type.as_deref(), Some("NOT_FOUND"))
+            }
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests[0].0, Method::POST);
+        assert_eq!(requests[0].1, "https://api.github.com/graphql");
+        let enterprise = Github::host("https://ghe.example.com/api/v3", "agent", None).unwrap();
+        assert_eq!(
+            enterprise.graphql_url(),
+            "https://ghe.example.com/api/graphql"
+        );
+    }
+
+    #[tokio::test]
+    async fn graphql_streams_walk_connections() {
+        let transport = FakeTransport::new(vec![
+            (
+                200,
+                r#"{"data":{"nodes":[1,2],"pageInfo":{"endCursor":"c1","hasNextPage":true}}}"#,
+            ),
+            (
+                200,
+                r#"{"data":{"nodes":[3],"pageInfo":{"endCursor":"c2","hasNextPage":false}}}"#,
+            ),
+        ]);
+        let nodes = github(&transport)
+            .graphql_stream(
+                "query($after: String) { .. }",
+                serde_json::json!({ "first": 2 }),
+                |connection: Connection<u32>| connection,
+            )
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(nodes, vec![1, 2, 3]);
+
+        let bodies = transport.bodies.lock().unwrap();
+        let variables = bodies
+            .iter()
+            .map(|body| {
+                let body: serde_json::Value =
+                    serde_json::from_slice(body.as_ref().unwrap()).unwrap();
+                body["variables"].clone()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            variables,
+            vec![
+                serde_json::json!({ "first": 2, "after": null }),
+                serde_json::json!({ "first": 2, "after": "c1" }),
+            ]
+        );
+    }
+}
@@ -6,26 +6,21 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{Future, Github};
+use crate::{Future, Github, Timestamp};
 
 /// Content-Type web hooks will receive
 /// deliveries in
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
 pub enum WebHookContentType {
     /// application/json
     #[serde(rename = "json")]
     Json,
     /// application/x-form-url-encoded
     #[serde(rename = "form")]
+    #[default]
     Form,
 }
 
-impl Default for WebHookContentType {
-    fn default() -> WebHookContentType {
-        WebHookContentType::Form
-    }
-}
-
 impl fmt::Display for WebHookContentType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
@@ -304,8 +299,8 @@ pub struct Hook {
     pub name: String,
     pub events: Vec<String>,
     pub config: ::serde_json::Value,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
     pub active: bool,
 }
 
@@ -1,17 +1,49 @@
 //! Implements <https://tools.ietf.org/html/rfc7232> Conditional Requests
+//!
+//! Two caches are provided: [InMemoryCache](struct.InMemoryCache.html), bounded by entry count
+//! and size and suited to long running processes, and
+//! [FileBasedCache](struct.FileBasedCache.html), which persists responses across runs and may
+//! be bounded by size and age with its [builder](struct.FileBasedCache.html#method.builder).
 use std::collections::hash_map::DefaultHasher;
-use std::ffi::OsStr;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{OsStr, OsString};
 use std::fmt::Debug;
 use std::fs;
 use std::hash::{Hash, Hasher};
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
 use http::Uri;
-use log::trace;
+use log::{debug, trace};
 
 use crate::{Error, Result};
 
+/// How a client uses its cache
+///
+/// Responses served from the cache without revalidation are marked stale, see
+/// [Response#is_stale](../response/struct.Response.html#method.is_stale).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Revalidate cached responses with GitHub, serving them only once GitHub reports them
+    /// unchanged. This is the default
+    #[default]
+    Online,
+    /// As `Online`, but serve cached responses to GET requests which fail with a network error
+    /// or a server error
+    StaleIfError,
+    /// Never send requests, serving GET requests from the cache and failing all others with
+    /// `Error::Offline`
+    Offline,
+}
+
+/// Minimum time between two sweeps of a `FileBasedCache` triggered by caching responses
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Marks the names of files holding responses to uris with a query, named by its hash
+const QUERY_HASH_SUFFIX: &str = ".query";
+
 pub type BoxedHttpCache = Box<dyn HttpCache + Send + Sync>;
 
 pub trait HttpCache: HttpCacheClone + Debug {
@@ -25,6 +57,24 @@ pub trait HttpCache: HttpCacheClone + Debug {
     fn lookup_etag(&self, uri: &str) -> Result<String>;
     fn lookup_body(&self, uri: &str) -> Result<String>;
     fn lookup_next_link(&self, uri: &str) -> Result<Option<String>>;
+
+    /// Remove the cached responses for `uri`, whatever their query
+    ///
+    /// The default implementation does nothing, leaving entries to be replaced once GitHub
+    /// responds with a new etag
+    fn invalidate(&self, _uri: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Remove the cached responses for `prefix` and all uris beneath it, whatever their query,
+    /// e.g. `https://api.github.com/repos/octocat/hello-world` for everything cached about a
+    /// repository
+    ///
+    /// The default implementation does nothing, leaving entries to be replaced once GitHub
+    /// responds with a new etag
+    fn purge_prefix(&self, _prefix: &str) -> Result<()> {
+        Ok(())
+    }
 }
 
 impl dyn HttpCache {
@@ -37,6 +87,11 @@ impl dyn HttpCache {
         dir.push(".hubcaps/cache");
         Box::new(FileBasedCache::new(dir))
     }
+
+    /// A cache held in memory, holding at most `max_entries` responses totalling `max_bytes`
+    pub fn in_memory(max_entries: usize, max_bytes: usize) -> BoxedHttpCache {
+        Box::new(InMemoryCache::new(max_entries, max_bytes))
+    }
 }
 
 impl Clone for BoxedHttpCache {
@@ -63,15 +118,294 @@ impl HttpCache for NoCache {
     }
 }
 
+/// A cache held in memory which evicts the least recently used responses once it holds more
+/// than a maximum number of entries or bytes
+///
+/// Clones share the same entries, so a single cache serves all clients built with it.
+#[derive(Clone, Debug)]
+pub struct InMemoryCache {
+    inner: Arc<Mutex<Lru>>,
+}
+
+#[derive(Debug)]
+struct Lru {
+    max_entries: usize,
+    max_bytes: usize,
+    bytes: usize,
+    clock: u64,
+    entries: HashMap<String, Entry>,
+    /// uris keyed by the clock value of their last use
+    recency: BTreeMap<u64, String>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    body: String,
+    etag: String,
+    next_link: Option<String>,
+    used: u64,
+}
+
+impl Entry {
+    fn size(&self, uri: &str) -> usize {
+        uri.len()
+            + self.body.len()
+            + self.etag.len()
+            + self.next_link.as_ref().map_or(0, String::len)
+    }
+}
+
+impl InMemoryCache {
+    pub fn new(max_entries: usize, max_bytes: usize) -> InMemoryCache {
+        InMemoryCache {
+            inner: Arc::new(Mutex::new(Lru {
+                max_entries,
+                max_bytes,
+                bytes: 0,
+                clock: 0,
+                entries: HashMap::new(),
+                recency: BTreeMap::new(),
+            })),
+        }
+    }
+
+    /// The number of responses cached
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The total size of the responses cached, in bytes
+    pub fn size(&self) -> usize {
+        self.inner.lock().unwrap().bytes
+    }
+
+    fn lookup<T>(&self, uri: &str, f: impl FnOnce(&Entry) -> T) -> Result<T> {
+        let mut lru = self.inner.lock().unwrap();
+        lru.touch(uri)
+            .map(f)
+            .map_or_else(|| no_read("Not cached"), Ok)
+    }
+}
+
+impl Lru {
+    fn touch(&mut self, uri: &str) -> Option<&Entry> {
+        self.clock += 1;
+        let clock = self.clock;
+        let entry = self.entries.get_mut(uri)?;
+        self.recency.remove(&entry.used);
+        self.recency.insert(clock, uri.to_owned());
+        entry.used = clock;
+        Some(entry)
+    }
+
+    fn remove(&mut self, uri: &str) {
+        if let Some(entry) = self.entries.remove(uri) {
+            self.recency.remove(&entry.used);
+            self.bytes -= entry.size(uri);
+        }
+    }
+
+    fn remove_matching(&mut self, matches: impl Fn(&str) -> bool) {
+        let uris = self
+            .entries
+            .keys()
+            .filter(|uri| matches(uri))
+            .cloned()
+            .collect::<Vec<_>>();
+        for uri in uris {
+            trace!("invalidating {} in cache", uri);
+            self.remove(&uri);
+        }
+    }
+
+    fn insert(&mut self, uri: String, mut entry: Entry) {
+        self.remove(&uri);
+        let size = entry.size(&uri);
+        if size > self.max_bytes || self.max_entries == 0 {
+            return;
+        }
+        while self.entries.len() >= self.max_entries || self.bytes + size > self.max_bytes {
+            let lru = match self.recency.values().next() {
+                Some(uri) => uri.clone(),
+                None => break,
+            };
+            trace!("evicting {} from cache", lru);
+            self.remove(&lru);
+        }
+        self.clock += 1;
+        entry.used = self.clock;
+        self.recency.insert(self.clock, uri.clone());
+        self.bytes += size;
+        self.entries.insert(uri, entry);
+    }
+}
+
+impl HttpCache for InMemoryCache {
+    fn cache_response(
+        &self,
+        uri: &str,
+        body: &[u8],
+        etag: &[u8],
+        next_link: &Option<String>,
+    ) -> Result<()> {
+        let entry = Entry {
+            body: String::from_utf8_lossy(body).into_owned(),
+            etag: String::from_utf8_lossy(etag).into_owned(),
+            next_link: next_link.clone(),
+            used: 0,
+        };
+        self.inner.lock().unwrap().insert(uri.to_owned(), entry);
+        Ok(())
+    }
+
+    fn lookup_etag(&self, uri: &str) -> Result<String> {
+        self.lookup(uri, |entry| entry.etag.clone())
+    }
+
+    fn lookup_body(&self, uri: &str) -> Result<String> {
+        self.lookup(uri, |entry| entry.body.clone())
+    }
+
+    fn lookup_next_link(&self, uri: &str) -> Result<Option<String>> {
+        self.lookup(uri, |entry| entry.next_link.clone())
+    }
+
+    fn invalidate(&self, uri: &str) -> Result<()> {
+        let path = without_query(uri);
+        self.inner
+            .lock()
+            .unwrap()
+            .remove_matching(|cached| without_query(cached) == path);
+        Ok(())
+    }
+
+    fn purge_prefix(&self, prefix: &str) -> Result<()> {
+        let prefix = without_query(prefix).trim_end_matches('/');
+        self.inner.lock().unwrap().remove_matching(|cached| {
+            let path = without_query(cached);
+            path == prefix || (path.starts_with(prefix) && path[prefix.len()..].starts_with('/'))
+        });
+        Ok(())
+    }
+}
+
+/// A cache storing responses as files under a root directory
+///
+/// By default entries are kept forever. A cache built with a maximum size or time to live is
+/// swept of expired entries, then of the oldest entries until it fits its maximum size, at
+/// most once a minute as responses are cached, or whenever [sweep](#method.sweep) is called.
 #[derive(Clone, Debug)]
 pub struct FileBasedCache {
     root: PathBuf,
+    max_bytes: Option<u64>,
+    ttl: Option<Duration>,
+    last_sweep: Arc<Mutex<Option<Instant>>>,
 }
 
 impl FileBasedCache {
     #[doc(hidden)] // public for integration testing only
     pub fn new<P: Into<PathBuf>>(root: P) -> FileBasedCache {
-        FileBasedCache { root: root.into() }
+        FileBasedCache {
+            root: root.into(),
+            max_bytes: None,
+            ttl: None,
+            last_sweep: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn builder<P: Into<PathBuf>>(root: P) -> FileBasedCacheBuilder {
+        FileBasedCacheBuilder(FileBasedCache::new(root))
+    }
+
+    /// Remove expired entries, then the least recently cached entries until the cache fits
+    /// its maximum size
+    pub fn sweep(&self) -> Result<()> {
+        *self.last_sweep.lock().unwrap() = Some(Instant::now());
+        if self.max_bytes.is_none() && self.ttl.is_none() {
+            return Ok(());
+        }
+        let mut entries = HashMap::<PathBuf, (SystemTime, u64)>::new();
+        let mut dirs = vec![self.root.join("v1")];
+        while let Some(dir) = dirs.pop() {
+            let listing = match fs::read_dir(&dir) {
+                Ok(listing) => listing,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            for file in listing {
+                let file = file?;
+                let metadata = file.metadata()?;
+                if metadata.is_dir() {
+                    dirs.push(file.path());
+                    continue;
+                }
+                // the files of an entry share a path, differing in extension only
+                let entry = entries
+                    .entry(file.path().with_extension("json"))
+                    .or_insert((SystemTime::UNIX_EPOCH, 0));
+                entry.0 = entry.0.max(metadata.modified()?);
+                entry.1 += metadata.len();
+            }
+        }
+        let mut entries = entries.into_iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(_, (modified, _))| *modified);
+        let mut bytes = entries.iter().map(|(_, (_, size))| size).sum::<u64>();
+        for (path, (modified, size)) in entries {
+            let expired = self.is_expired(modified);
+            if !expired && self.max_bytes.is_none_or(|max_bytes| bytes <= max_bytes) {
+                continue;
+            }
+            trace!("evicting {} from cache", path.display());
+            remove_entry(&path)?;
+            bytes -= size;
+        }
+        Ok(())
+    }
+
+    fn is_expired(&self, modified: SystemTime) -> bool {
+        self.ttl
+            .is_some_and(|ttl| modified.elapsed().is_ok_and(|age| age > ttl))
+    }
+
+    fn sweep_if_due(&self) {
+        if self.max_bytes.is_none() && self.ttl.is_none() {
+            return;
+        }
+        let due = self
+            .last_sweep
+            .lock()
+            .unwrap()
+            .is_none_or(|last_sweep| last_sweep.elapsed() >= SWEEP_INTERVAL);
+        if due {
+            if let Err(e) = self.sweep() {
+                // failing to sweep isn't fatal, so just log & swallow the error
+                debug!("Failed to sweep cache: {}", e);
+            }
+        }
+    }
+}
+
+pub struct FileBasedCacheBuilder(FileBasedCache);
+
+impl FileBasedCacheBuilder {
+    /// Evict the least recently cached entries once the cache grows beyond `max_bytes`
+    pub fn max_bytes(&mut self, max_bytes: u64) -> &mut Self {
+        self.0.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Expire entries cached longer than `ttl` ago
+    pub fn ttl(&mut self, ttl: Duration) -> &mut Self {
+        self.0.ttl = Some(ttl);
+        self
+    }
+
+    pub fn build(&self) -> FileBasedCache {
+        self.0.clone()
     }
 }
 
@@ -83,7 +417,7 @@ impl HttpCache for FileBasedCache {
         etag: &[u8],
         next_link: &Option<String>,
     ) -> Result<()> {
-        let mut path = cache_path(&self.root, &uri, "json");
+        let mut path = cache_path(&self.root, uri, "json");
         trace!("caching body at path: {}", path.display());
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
@@ -95,11 +429,16 @@ impl HttpCache for FileBasedCache {
             path.set_extension("next_link");
             fs::write(&path, next_link)?;
         }
+        self.sweep_if_due();
         Ok(())
     }
 
     fn lookup_etag(&self, uri: &str) -> Result<String> {
-        read_to_string(cache_path(&self.root, uri, "etag"))
+        let path = cache_path(&self.root, uri, "etag");
+        if self.ttl.is_some() && self.is_expired(fs::metadata(&path)?.modified()?) {
+            return no_read("Cached etag expired");
+        }
+        read_to_string(path)
     }
 
     fn lookup_body(&self, uri: &str) -> Result<String> {
@@ -114,6 +453,71 @@ impl HttpCache for FileBasedCache {
             Ok(None)
         }
     }
+
+    fn invalidate(&self, uri: &str) -> Result<()> {
+        let uri = without_query(uri);
+        trace!("invalidating {} in cache", uri);
+        let dir = resource_dir(&self.root, &absolute_uri(uri)?);
+        remove_entry(&cache_path(&self.root, uri, "json"))?;
+        // responses to the uri with a query are stored beneath it, named by the query's hash
+        let listing = match fs::read_dir(&dir) {
+            Ok(listing) => listing,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        for file in listing {
+            let file = file?.path();
+            let is_query_hash = file
+                .file_stem()
+                .and_then(OsStr::to_str)
+                .and_then(|stem| stem.strip_suffix(QUERY_HASH_SUFFIX))
+                .is_some_and(|hash| {
+                    hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+                });
+            if is_query_hash && file.is_file() {
+                remove_entry(&file)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn purge_prefix(&self, prefix: &str) -> Result<()> {
+        let prefix = without_query(prefix).trim_end_matches('/');
+        trace!("purging {} from cache", prefix);
+        let dir = resource_dir(&self.root, &absolute_uri(prefix)?);
+        remove_entry(&cache_path(&self.root, prefix, "json"))?;
+        match fs::remove_dir_all(&dir) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Remove the files of the entry stored at `path`, whatever its extension
+fn remove_entry(path: &Path) -> Result<()> {
+    for extension in &["json", "etag", "next_link"] {
+        match fs::remove_file(path.with_extension(extension)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Parse a uri to remove cached responses for, which must be absolute like the uris cached
+fn absolute_uri(uri: &str) -> Result<Uri> {
+    let parsed = uri.parse::<Uri>().map_err(http::Error::from)?;
+    if parsed.scheme().is_none() || parsed.authority().is_none() {
+        return Err(Error::from(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} isn't an absolute URI", uri),
+        )));
+    }
+    Ok(parsed)
+}
+
+fn without_query(uri: &str) -> &str {
+    uri.split(['?', '#']).next().unwrap_or(uri)
 }
 
 /// Construct the cache path for the given URI and extension, from an initial directory.
@@ -129,26 +533,41 @@ impl HttpCache for FileBasedCache {
 /// );
 /// assert_eq!(
 ///     cache_path(&PathBuf::from("/home/.hubcaps/cache"), "https://api.github.com/users/dwijnand/repos?page=2", "json"),
-///     PathBuf::from("/home/.hubcaps/cache/v1/https/api.github.com/users/dwijnand/repos/6dd58bde8abb0869.json"),
+///     PathBuf::from("/home/.hubcaps/cache/v1/https/api.github.com/users/dwijnand/repos/6dd58bde8abb0869.query.json"),
 /// );
 /// assert_eq!(
 ///     cache_path(&PathBuf::from("/home/.hubcaps/cache"), "https://api.github.com/users/dwijnand/repos?page=2&per_page=5", "json"),
-///     PathBuf::from("/home/.hubcaps/cache/v1/https/api.github.com/users/dwijnand/repos/d862dcd2d85cebca.json"),
+///     PathBuf::from("/home/.hubcaps/cache/v1/https/api.github.com/users/dwijnand/repos/d862dcd2d85cebca.query.json"),
 /// );
 /// ```
 #[doc(hidden)] // public for doc testing only
 pub fn cache_path<S: AsRef<OsStr>>(dir: &Path, uri: &str, extension: S) -> PathBuf {
     let uri = uri.parse::<Uri>().expect("Expected a URI");
+    let mut path = resource_dir(dir, &uri);
+    match uri.query() {
+        Some(query) => {
+            // marked, so they aren't mistaken for sub-resources named like a hash
+            let mut name = OsString::from(hash1(query, DefaultHasher::new())); // fa269019d5035d5f
+            name.push(QUERY_HASH_SUFFIX); // .query
+            name.push(".");
+            name.push(extension); // .json
+            path.push(name);
+        }
+        None => {
+            path.set_extension(extension); // .json
+        }
+    }
+    path
+}
+
+/// The directory holding the responses for the given URI with a query, and for URIs beneath it
+fn resource_dir(dir: &Path, uri: &Uri) -> PathBuf {
     let parts = uri.clone().into_parts();
     let mut path = dir.to_path_buf();
     path.push("v1");
     path.push(parts.scheme.expect("no URI scheme").as_str()); // https
     path.push(parts.authority.expect("no URI authority").as_str()); // api.github.com
     path.push(Path::new(&uri.path()[1..])); // users/dwijnand/repos
-    if let Some(query) = uri.query() {
-        path.push(hash1(query, DefaultHasher::new())); // fa269019d5035d5f
-    }
-    path.set_extension(extension); // .json
     path
 }
 
@@ -196,3 +615,216 @@ fn hash1<A: Hash, H: Hasher>(x: A, mut hasher: H) -> String {
 pub fn u64_to_padded_hex(x: u64) -> String {
     format!("{:016x}", x)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeTransport;
+    use crate::{CacheMode, Error, Github, DEFAULT_HOST};
+    use http::header::{HeaderMap, HeaderValue, ETAG};
+    use reqwest::Client;
+
+    #[test]
+    fn evicts_least_recently_used_responses() {
+        let cache = InMemoryCache::new(2, 1024);
+        for uri in &["https://a/1", "https://a/2"] {
+            cache.cache_response(uri, b"[]", b"etag", &None).unwrap();
+        }
+        cache.lookup_etag("https://a/1").unwrap();
+        cache
+            .cache_response("https://a/3", b"[]", b"etag", &None)
+            .unwrap();
+        assert!(cache.lookup_body("https://a/1").is_ok());
+        assert!(cache.lookup_body("https://a/2").is_err());
+        assert!(cache.lookup_body("https://a/3").is_ok());
+
+        let body = vec![b'x'; 990];
+        cache
+            .cache_response("https://a/4", &body, b"etag", &Some("https://a/5".into()))
+            .unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            cache.lookup_next_link("https://a/4").unwrap().as_deref(),
+            Some("https://a/5")
+        );
+        cache
+            .cache_response("https://a/6", &[b'x'; 2048], b"etag", &None)
+            .unwrap();
+        assert!(cache.lookup_body("https://a/6").is_err());
+        assert_eq!(cache.size(), "https://a/4".len() + 990 + 4 + 11);
+    }
+
+    fn invalidates_resources(cache: &dyn HttpCache) {
+        let repo = "https://api.github.com/repos/o/hello.world";
+        for uri in &[
+            format!("{}/commits?sha=main", repo),
+            format!("{}/commits/0123456789abcdef", repo),
+            format!("{}/issues", repo),
+            format!("{}/issues?state=all", repo),
+            format!("{}/issues/1", repo),
+            format!("{}/issues/1/comments", repo),
+            format!("{}/issues/2", repo),
+            format!("{}/labels", repo),
+        ] {
+            cache.cache_response(uri, b"[]", b"etag", &None).unwrap();
+        }
+        let cached = |path: &str| cache.lookup_body(&format!("{}{}", repo, path)).is_ok();
+
+        cache.invalidate(&format!("{}/commits", repo)).unwrap();
+        assert!(!cached("/commits?sha=main"));
+        assert!(cached("/commits/0123456789abcdef"));
+
+        cache.invalidate(&format!("{}/issues", repo)).unwrap();
+        assert!(!cached("/issues"));
+        assert!(!cached("/issues?state=all"));
+        assert!(cached("/issues/1"));
+
+        cache.purge_prefix(&format!("{}/issues/1", repo)).unwrap();
+        assert!(!cached("/issues/1"));
+        assert!(!cached("/issues/1/comments"));
+        assert!(cached("/issues/2"));
+
+        cache.purge_prefix(repo).unwrap();
+        assert!(!cached("/issues/2"));
+        assert!(!cached("/labels"));
+    }
+
+    #[test]
+    fn invalidates_in_memory_responses() {
+        invalidates_resources(&InMemoryCache::new(10, 1024));
+    }
+
+    #[test]
+    fn invalidates_file_based_responses() {
+        let root = std::env::temp_dir().join(format!("hubcaps-purge-{}", std::process::id()));
+        let cache = FileBasedCache::new(&root);
+        invalidates_resources(&cache);
+        assert!(cache.invalidate("/repos/o/r/issues").is_err());
+        assert!(cache.purge_prefix("not a uri").is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn sweeps_files_beyond_max_size() {
+        let root = std::env::temp_dir().join(format!("hubcaps-cache-{}", std::process::id()));
+        let cache = FileBasedCache::builder(&root).max_bytes(100).build();
+        for page in 1..=3 {
+            let uri = format!("https://api.github.com/user/repos?page={}", page);
+            cache
+                .cache_response(&uri, &[b'x'; 40], b"etag", &None)
+                .unwrap();
+            // give entries distinct modification times
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        cache.sweep().unwrap();
+        let cached = (1..=3)
+            .map(|page| {
+                let uri = format!("https://api.github.com/user/repos?page={}", page);
+                cache.lookup_body(&uri).is_ok()
+            })
+            .collect::<Vec<_>>();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(cached, vec![false, true, true]);
+
+        let expiring = FileBasedCache::builder(&root)
+            .ttl(Duration::from_secs(0))
+            .build();
+        expiring
+            .cache_response("https://api.github.com/user", b"{}", b"etag", &None)
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(expiring.lookup_etag("https://api.github.com/user").is_err());
+        expiring.sweep().unwrap();
+        assert!(expiring.lookup_body("https://api.github.com/user").is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn mutations_invalidate_cached_responses() {
+        let cache = InMemoryCache::new(10, 1024);
+        for uri in &[
+            "https://api.github.com/repos/o/r/issues",
+            "https://api.github.com/repos/o/r/issues/1",
+            "https://api.github.com/repos/o/r/issues/2",
+        ] {
+            cache.cache_response(uri, b"{}", b"etag", &None).unwrap();
+        }
+        let transport = FakeTransport::new(vec![(200, r#"{"id":1}"#), (201, r#"{"id":3}"#)])
+            .with_headers(vec![HeaderMap::new(), {
+                let mut headers = HeaderMap::new();
+                headers.insert(ETAG, HeaderValue::from_static("\"created\""));
+                headers
+            }]);
+        let mut github = Github::custom(
+            DEFAULT_HOST,
+            "agent",
+            None,
+            Client::new(),
+            Box::new(cache.clone()),
+        );
+        github.set_transport(transport);
+        github
+            .patch::<serde_json::Value>("/repos/o/r/issues/1", b"{}".to_vec())
+            .await
+            .unwrap();
+        github
+            .post::<serde_json::Value>("/repos/o/r/issues", b"{}".to_vec())
+            .await
+            .unwrap();
+        assert!(
+            cache
+                .lookup_body("https://api.github.com/repos/o/r/issues")
+                .is_err(),
+            "the list isn't repopulated from the response to the POST"
+        );
+        assert!(cache
+            .lookup_body("https://api.github.com/repos/o/r/issues/1")
+            .is_err());
+        assert!(cache
+            .lookup_body("https://api.github.com/repos/o/r/issues/2")
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn serves_stale_responses() {
+        let cache = InMemoryCache::new(10, 1024);
+        cache
+            .cache_response(
+                "https://api.github.com/user",
+                br#"{"login":"octocat"}"#,
+                b"etag",
+                &None,
+            )
+            .unwrap();
+        let transport = FakeTransport::new(vec![(502, r#"{"message":"Bad Gateway"}"#)]);
+        let mut github =
+            Github::custom(DEFAULT_HOST, "agent", None, Client::new(), Box::new(cache));
+        github.set_transport(transport.clone());
+        github.set_cache_mode(CacheMode::StaleIfError);
+        let user = github
+            .with_response(|github| github.get::<serde_json::Value>("/user"))
+            .await
+            .unwrap();
+        assert!(user.is_stale());
+        assert_eq!(user.entity["login"], "octocat");
+
+        github.set_cache_mode(CacheMode::Offline);
+        let user = github
+            .with_response(|github| github.get::<serde_json::Value>("/user"))
+            .await
+            .unwrap();
+        assert!(user.is_stale());
+        match github.get::<serde_json::Value>("/user/repos").await {
+            Err(Error::Offline) => (),
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+        match github
+            .post::<serde_json::Value>("/user/repos", b"{}".to_vec())
+            .await
+        {
+            Err(Error::Offline) => (),
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+        assert_eq!(transport.requests.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,202 @@
+//! `tracing` instrumentation of API calls
+//!
+//! With the `tracing` feature enabled every API call runs in a `github.request` span recording
+//! its method, templated path, status, latency, request id and remaining rate limit budget.
+//! Streams over paginated listings run in a `github.stream` span with a `github.page` child span
+//! per page fetched. Without the feature all of this compiles down to nothing.
+use std::future::Future;
+use std::time::Instant;
+
+use http::header::HeaderMap;
+use http::{Method, StatusCode};
+#[cfg(feature = "tracing")]
+use tracing::{field, Instrument};
+
+use crate::Error;
+#[cfg(feature = "tracing")]
+use crate::{X_GITHUB_REQUEST_ID, X_RATELIMIT_REMAINING};
+
+#[cfg(feature = "tracing")]
+pub(crate) type Span = tracing::Span;
+
+#[cfg(not(feature = "tracing"))]
+#[derive(Clone, Debug)]
+pub(crate) struct Span;
+
+/// Run the future making an API call in a span describing it
+#[cfg(feature = "tracing")]
+pub(crate) fn call<F>(method: &Method, uri: &str, future: F) -> impl Future<Output = F::Output>
+where
+    F: Future,
+{
+    future.instrument(tracing::info_span!(
+        "github.request",
+        http.method = %method,
+        http.path = %path_template(uri),
+        http.status_code = field::Empty,
+        latency_ms = field::Empty,
+        attempts = field::Empty,
+        github.request_id = field::Empty,
+        github.ratelimit_remaining = field::Empty,
+        error = field::Empty,
+    ))
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn call<F>(_method: &Method, _uri: &str, future: F) -> impl Future<Output = F::Output>
+where
+    F: Future,
+{
+    future
+}
+
+/// Record the final response of the API call in the current span
+#[cfg(feature = "tracing")]
+pub(crate) fn record_response(
+    status: StatusCode,
+    headers: &HeaderMap,
+    started: Instant,
+    attempts: u32,
+) {
+    let span = Span::current();
+    span.record("http.status_code", status.as_u16());
+    span.record("latency_ms", started.elapsed().as_millis() as u64);
+    span.record("attempts", attempts);
+    if let Some(request_id) = headers
+        .get(X_GITHUB_REQUEST_ID)
+        .and_then(|v| v.to_str().ok())
+    {
+        span.record("github.request_id", request_id);
+    }
+    if let Some(remaining) = headers
+        .get(X_RATELIMIT_REMAINING)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u32>().ok())
+    {
+        span.record("github.ratelimit_remaining", remaining);
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn record_response(
+    _status: StatusCode,
+    _headers: &HeaderMap,
+    _started: Instant,
+    _attempts: u32,
+) {
+}
+
+/// Record an API call which failed without a response in the current span
+#[cfg(feature = "tracing")]
+pub(crate) fn record_error(error: &Error, started: Instant, attempts: u32) {
+    let span = Span::current();
+    span.record("latency_ms", started.elapsed().as_millis() as u64);
+    span.record("attempts", attempts);
+    span.record("error", tracing::field::display(error));
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn record_error(_error: &Error, _started: Instant, _attempts: u32) {}
+
+/// A span covering all pages fetched by a stream over a paginated listing
+#[cfg(feature = "tracing")]
+pub(crate) fn stream(uri: &str) -> Span {
+    tracing::info_span!("github.stream", http.path = %path_template(uri))
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn stream(_uri: &str) -> Span {
+    Span
+}
+
+/// Run the future fetching a page of a stream in a child span of the stream's span
+#[cfg(feature = "tracing")]
+pub(crate) fn page<F>(stream: &Span, page: u32, future: F) -> impl Future<Output = F::Output>
+where
+    F: Future,
+{
+    future.instrument(tracing::info_span!(parent: stream, "github.page", page))
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn page<F>(_stream: &Span, _page: u32, future: F) -> impl Future<Output = F::Output>
+where
+    F: Future,
+{
+    future
+}
+
+/// Replace the identifying segments of an API path with placeholders, so that calls to the
+/// same endpoint share a path, e.g. `/repos/{owner}/{repo}/issues/{id}`
+///
+/// This is a heuristic based on the segments preceding a value rather than on GitHub's route
+/// definitions, so some values in uncommon paths are left in place.
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+pub(crate) fn path_template(uri: &str) -> String {
+    let path = uri
+        .find("://")
+        .and_then(|scheme| {
+            uri[scheme + 3..]
+                .find('/')
+                .map(|path| &uri[scheme + 3 + path..])
+        })
+        .unwrap_or(uri);
+    let path = path.split(['?', '#']).next().unwrap_or("");
+    let mut template = Vec::new();
+    let mut segments = path.split('/').peekable();
+    while let Some(segment) = segments.next() {
+        let placeholder = match template.last().map(String::as_str) {
+            Some("repos") if segments.peek().is_some() => {
+                segments.next();
+                template.push("{owner}".to_owned());
+                "{repo}"
+            }
+            Some("users") => "{username}",
+            Some("orgs") => "{org}",
+            Some("labels") => "{name}",
+            Some("branches") => "{branch}",
+            Some("statuses") | Some("commits") if is_sha(segment) => "{sha}",
+            Some("contents") => {
+                // the remaining segments are a file path
+                segments.by_ref().for_each(drop);
+                "{path}"
+            }
+            _ if !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()) => "{id}",
+            _ => segment,
+        };
+        template.push(placeholder.to_owned());
+    }
+    template.join("/")
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+fn is_sha(segment: &str) -> bool {
+    segment.len() >= 7 && segment.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_paths() {
+        for (uri, template) in &[
+            (
+                "https://api.github.com/repos/octocat/hello-world/issues/12/labels/bug?page=2",
+                "/repos/{owner}/{repo}/issues/{id}/labels/{name}",
+            ),
+            (
+                "https://ghe.example.com/api/v3/repos/o/r/commits/6dcb09b5b57875f334f61aebed695e2e4193db5e/statuses",
+                "/api/v3/repos/{owner}/{repo}/commits/{sha}/statuses",
+            ),
+            (
+                "/repos/o/r/contents/src/lib.rs",
+                "/repos/{owner}/{repo}/contents/{path}",
+            ),
+            ("/orgs/rust-lang/teams", "/orgs/{org}/teams"),
+            ("/user/repos", "/user/repos"),
+        ] {
+            assert_eq!(path_template(uri), *template);
+        }
+    }
+}
@@ -7,14 +7,16 @@ use url::form_urlencoded;
 
 use crate::comments::Comments;
 use crate::labels::Label;
+use crate::page::{Cursor, Page};
 use crate::users::User;
 use crate::utils::{percent_encode, PATH_SEGMENT};
-use crate::{Future, Github, SortDirection, Stream};
+use crate::{Future, Github, SortDirection, Stream, Timestamp};
 
 /// enum representation of github pull and issue state
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum State {
     /// Only open issues
+    #[default]
     Open,
     /// Only closed issues
     Closed,
@@ -33,16 +35,11 @@ impl fmt::Display for State {
     }
 }
 
-impl Default for State {
-    fn default() -> State {
-        State::Open
-    }
-}
-
 /// Sort options available for github issues
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum Sort {
     /// sort by creation time of issue
+    #[default]
     Created,
     /// sort by the last time issue was updated
     Updated,
@@ -61,12 +58,6 @@ impl fmt::Display for Sort {
     }
 }
 
-impl Default for Sort {
-    fn default() -> Sort {
-        Sort::Created
-    }
-}
-
 /// Provides access to assignee operations available for an individual issue
 pub struct IssueAssignees {
     github: Github,
@@ -296,6 +287,26 @@ impl Issues {
         }
         self.github.get_stream(&uri.join("?"))
     }
+
+    /// provides a stream over all pages of issues, along with the cursor following each of
+    /// them, which can be used to resume the stream later with
+    /// [Github#resume_stream](../struct.Github.html#method.resume_stream)
+    pub fn iter_resumable(&self, options: &IssueListOptions) -> Stream<(Cursor, Issue)> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.resume_stream(&Cursor::new(uri.join("?")))
+    }
+
+    /// Return a single page of issues, which can be used to navigate to the pages around it
+    pub fn page(&self, options: &IssueListOptions) -> Future<Page<Issue>> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page(&uri.join("?"))
+    }
 }
 
 // representations
@@ -399,9 +410,9 @@ impl IssueListOptionsBuilder {
 
     pub fn since<S>(&mut self, since: S) -> &mut Self
     where
-        S: Into<String>,
+        S: Into<Timestamp>,
     {
-        self.0.params.insert("since", since.into());
+        self.0.params.insert("since", since.into().to_string());
         self
     }
 
@@ -474,9 +485,9 @@ pub struct Issue {
     pub locked: bool,
     pub comments: u64,
     pub pull_request: Option<PullRef>,
-    pub closed_at: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
+    pub closed_at: Option<Timestamp>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
     pub assignees: Vec<User>,
 }
 
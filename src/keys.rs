@@ -4,7 +4,7 @@
 //! for motivation and use
 use serde::{Deserialize, Serialize};
 
-use crate::{Future, Github};
+use crate::{Future, Github, Timestamp};
 
 pub struct Keys {
     github: Github,
@@ -55,7 +55,7 @@ pub struct Key {
     pub key: String,
     pub title: String,
     pub verified: bool,
-    pub created_at: String,
+    pub created_at: Timestamp,
     pub read_only: bool,
 }
 
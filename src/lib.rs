@@ -36,6 +36,19 @@
 //! Hubcaps supports two types of interfaces for working with listings. `list(...)` interfaces return the first
 //! ( often enough ) list of entities. Alternatively for listings that require > 30 items you may wish to
 //! use the `iter(..)` variant which returns a `futures::Stream` over all entities in a paginated set.
+//! Streams fetch one page after another unless a client is configured to fetch several at once
+//! with [Github#set_page_concurrency](struct.Github.html#method.set_page_concurrency).
+//! Interfaces like [Issues#page](issues/struct.Issues.html#method.page) return a single
+//! [Page](page/struct.Page.html) of entities instead, which knows its position in the set and
+//! can fetch the pages around it.
+//!
+//! Endpoints hubcaps doesn't provide an interface for yet can still be called through
+//! [Github#raw_request](struct.Github.html#method.raw_request), and listed page by page
+//! with [Github#raw_stream](struct.Github.html#method.raw_stream).
+//!
+//! Any call can also be made through
+//! [Github#with_response](struct.Github.html#method.with_response) to read the status and
+//! headers of its response, like the `ETag`, rate limit or pagination links, alongside the entity.
 //!
 //! # Errors
 //!
@@ -58,6 +71,32 @@
 //! GitHub's rate limits, enable debug looking and look for "x-rate-limit"
 //! log patterns sourced from this crate
 //!
+//! Long running jobs may instead register a
+//! [Governor](rate_limit/struct.Governor.html) with
+//! [Github#set_governor](struct.Github.html#method.set_governor). A governor tracks the
+//! remaining budget of each rate limited resource and waits for the reset rather than failing
+//! once a budget is exhausted.
+//!
+//! Crawls spanning more requests than one token's budget allows can spread them across several
+//! tokens or app installations with a
+//! [CredentialPool](rate_limit/struct.CredentialPool.html), which fails over to another member
+//! whenever one runs out of budget.
+//!
+//! Responses triggering GitHub's secondary rate limits, usually caused by bursts of content
+//! creating requests, are reported as
+//! [hubcaps::Error::SecondaryRateLimit](errors/enum.Error.html#variant.SecondaryRateLimit)
+//! carrying the duration to wait before retrying. Bots creating many issues, comments or labels
+//! can space out such requests by registering a [Pacer](rate_limit/struct.Pacer.html) with
+//! [Github#set_pacer](struct.Github.html#method.set_pacer).
+//!
+//! ## Retries
+//!
+//! By default requests are attempted once. Transient failures like `502`, `503` and `504`
+//! responses, timeouts or reset connections can be retried with exponential backoff by
+//! registering a [RetryPolicy](retry/struct.RetryPolicy.html) with
+//! [Github#set_retry_policy](struct.Github.html#method.set_retry_policy). The policy applies
+//! to every request, including each page fetched by `iter(..)` streams.
+//!
 //! # Features
 //!
 //! ## httpcache
@@ -76,7 +115,49 @@
 //! ```
 //!
 //! Then use the `Github::custom` constructor to provide a cache implementation. See
-//! the conditional_requests example in this crates github repository for an example usage
+//! the conditional_requests example in this crates github repository for an example usage.
+//! Long running processes may prefer a bounded cache, like
+//! [HttpCache::in_memory](http_cache/trait.HttpCache.html#method.in_memory) or a
+//! [FileBasedCache](http_cache/struct.FileBasedCache.html) with a maximum size and time to live.
+//! Cached responses can also keep serving GET requests while GitHub is unreachable, see
+//! [Github#set_cache_mode](struct.Github.html#method.set_cache_mode)
+//!
+//! Conditional requests can also be made per call without this feature, keeping etags wherever
+//! suits, with [Github#conditional](struct.Github.html#method.conditional).
+//!
+//! ## chrono
+//!
+//! Dates found in representations, like `Issue::created_at`, are
+//! [Timestamp](struct.Timestamp.html)s, parsed from the RFC 3339 strings GitHub responds with.
+//! The `chrono` feature flag backs them with `chrono::DateTime<Utc>` values and adds
+//! conversions from and to those, so options filtering by time, like
+//! `IssueListOptionsBuilder::since`, take them too.
+//!
+//! ## fake
+//!
+//! The `fake` feature flag provides [FakeGithub](fake/struct.FakeGithub.html), a local
+//! server which keeps repositories, issues, pull requests, labels, comments and statuses in
+//! memory. Point a client created with [Github#host](struct.Github.html#method.host) at it to
+//! test code using hubcaps end to end without access to GitHub.
+//!
+//! ## cassette
+//!
+//! The `cassette` feature flag provides [cassette](cassette/index.html) transports, which
+//! record the requests a client makes and the responses GitHub sends to a file, and replay
+//! them later without access to GitHub.
+//!
+//! ## blocking
+//!
+//! The `blocking` feature flag provides a [blocking](blocking/index.html) client mirroring
+//! `Github` and its most commonly used services, for programs which don't run an async runtime.
+//! Its operations return results directly and paginated listings are returned as iterators.
+//!
+//! ## tracing
+//!
+//! The `tracing` feature flag runs every API call in a `github.request`
+//! [tracing](https://docs.rs/tracing) span recording the method, templated path, status,
+//! latency, `x-github-request-id` and remaining rate limit budget of the call. Streams over
+//! paginated listings run in a `github.stream` span with a `github.page` child span per page.
 //!
 #![allow(missing_docs)] // todo: make this a deny eventually
 
@@ -85,13 +166,14 @@ use std::pin::Pin;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use futures::{future, prelude::*, stream, Future as StdFuture, Stream as StdStream};
 #[cfg(feature = "httpcache")]
-use http::header::IF_NONE_MATCH;
+use http::header::WARNING;
 use http::header::{HeaderMap, HeaderValue};
-use http::header::{ACCEPT, AUTHORIZATION, ETAG, LINK, USER_AGENT};
+use http::header::{ACCEPT, AUTHORIZATION, ETAG, LINK, RETRY_AFTER, USER_AGENT};
+use http::header::{IF_MODIFIED_SINCE, IF_NONE_MATCH};
 use http::{Method, StatusCode};
 #[cfg(feature = "httpcache")]
 use hyperx::header::LinkValue;
@@ -99,10 +181,11 @@ use hyperx::header::{qitem, Link, RelationType};
 use jsonwebtoken as jwt;
 use log::{debug, error, trace};
 use mime::Mime;
+use reqwest::Client;
 use reqwest::Url;
-use reqwest::{Body, Client};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use tokio::time::delay_for;
 
 #[doc(hidden)] // public for doc testing and integration testing only
 #[cfg(feature = "httpcache")]
@@ -111,49 +194,76 @@ pub mod http_cache;
 mod macros; // expose json! macro to child modules
 pub mod activity;
 pub mod app;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod branches;
+#[cfg(feature = "cassette")]
+pub mod cassette;
 pub mod checks;
 pub mod collaborators;
 pub mod comments;
 pub mod content;
 pub mod deployments;
+pub mod discovery;
 pub mod errors;
+#[cfg(feature = "fake")]
+pub mod fake;
 pub mod gists;
 pub mod git;
+pub mod graphql;
 pub mod hooks;
+mod instrument;
 pub mod issues;
 pub mod keys;
 pub mod labels;
 pub mod membership;
+pub mod middleware;
 pub mod notifications;
+pub mod oauth;
 pub mod organizations;
+pub mod page;
 pub mod pull_commits;
 pub mod pulls;
 pub mod rate_limit;
 pub mod releases;
 pub mod repo_commits;
 pub mod repositories;
+pub mod response;
+pub mod retry;
 pub mod review_comments;
 pub mod review_requests;
 pub mod search;
 pub mod stars;
 pub mod statuses;
 pub mod teams;
+#[cfg(test)]
+mod testing;
+pub mod timestamp;
 pub mod traffic;
+pub mod transport;
 pub mod users;
 pub mod watching;
 
 pub use crate::errors::{Error, Result};
 #[cfg(feature = "httpcache")]
-pub use crate::http_cache::{BoxedHttpCache, HttpCache};
+pub use crate::http_cache::{BoxedHttpCache, CacheMode, HttpCache};
+pub use crate::timestamp::Timestamp;
 
 use crate::activity::Activity;
-use crate::app::App;
+use crate::app::{AccessTokenOptions, App};
+use crate::errors::ClientError;
 use crate::gists::{Gists, UserGists};
+use crate::graphql::Connection;
+use crate::middleware::Middleware;
+use crate::oauth::OAuth;
 use crate::organizations::{Organization, Organizations, UserOrganizations};
-use crate::rate_limit::RateLimit;
+use crate::page::{Cursor, Page};
+use crate::rate_limit::{CredentialPool, Governor, Pacer, RateLimit, Resource};
 use crate::repositories::{OrganizationRepositories, Repositories, Repository, UserRepositories};
+use crate::response::{Conditional, Conditions, Response};
+use crate::retry::{retry_after, RetryPolicy};
 use crate::search::Search;
+use crate::transport::{BoxedTransport, ReqwestTransport, Transport};
 use crate::users::Users;
 
 const DEFAULT_HOST: &str = "https://api.github.com";
@@ -162,6 +272,12 @@ const DEFAULT_HOST: &str = "https://api.github.com";
 const MAX_JWT_TOKEN_LIFE: time::Duration = time::Duration::from_secs(60 * 9);
 // 8 minutes so we refresh sooner than it actually expires
 const JWT_TOKEN_REFRESH_PERIOD: time::Duration = time::Duration::from_secs(60 * 8);
+// Refresh installation tokens 5 minutes ahead of their expiry to give some
+// buffer for clock drift and requests in flight
+const INSTALLATION_TOKEN_REFRESH_MARGIN: time::Duration = time::Duration::from_secs(60 * 5);
+// GitHub asks to wait at least a minute when a secondary rate limit
+// response comes without a Retry-After header
+const SECONDARY_RATE_LIMIT_RETRY_AFTER: Duration = Duration::from_secs(60);
 
 /// A type alias for `Futures` that may return `hubcaps::Errors`
 pub type Future<T> = Pin<Box<dyn StdFuture<Output = Result<T>> + Send>>;
@@ -169,10 +285,17 @@ pub type Future<T> = Pin<Box<dyn StdFuture<Output = Result<T>> + Send>>;
 /// A type alias for `Streams` that may result in `hubcaps::Errors`
 pub type Stream<T> = Pin<Box<dyn StdStream<Item = Result<T>> + Send>>;
 
-const X_GITHUB_REQUEST_ID: &str = "x-github-request-id";
-const X_RATELIMIT_LIMIT: &str = "x-ratelimit-limit";
-const X_RATELIMIT_REMAINING: &str = "x-ratelimit-remaining";
-const X_RATELIMIT_RESET: &str = "x-ratelimit-reset";
+/// `Warning` values marking responses served from the cache without revalidation
+#[cfg(feature = "httpcache")]
+const REVALIDATION_FAILED: &str = "111 - \"Revalidation Failed\"";
+#[cfg(feature = "httpcache")]
+const DISCONNECTED_OPERATION: &str = "112 - \"Disconnected Operation\"";
+
+pub(crate) const X_GITHUB_REQUEST_ID: &str = "x-github-request-id";
+pub(crate) const X_RATELIMIT_LIMIT: &str = "x-ratelimit-limit";
+pub(crate) const X_RATELIMIT_REMAINING: &str = "x-ratelimit-remaining";
+pub(crate) const X_RATELIMIT_RESET: &str = "x-ratelimit-reset";
+pub(crate) const X_RATELIMIT_RESOURCE: &str = "x-ratelimit-resource";
 
 pub(crate) mod utils {
     pub use percent_encoding::percent_encode;
@@ -189,20 +312,15 @@ pub(crate) mod utils {
 
 /// GitHub defined Media types
 /// See [this doc](https://developer.github.com/v3/media/) for more for more information
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
 pub enum MediaType {
     /// Return json (the default)
+    #[default]
     Json,
     /// Return json in preview form
     Preview(&'static str),
 }
 
-impl Default for MediaType {
-    fn default() -> MediaType {
-        MediaType::Json
-    }
-}
-
 impl From<MediaType> for Mime {
     fn from(media: MediaType) -> Mime {
         match media {
@@ -228,9 +346,10 @@ pub enum AuthenticationConstraint {
 }
 
 /// enum representation of Github list sorting options
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum SortDirection {
     /// Sort in ascending order (the default)
+    #[default]
     Asc,
     /// Sort in descending order
     Desc,
@@ -246,12 +365,6 @@ impl fmt::Display for SortDirection {
     }
 }
 
-impl Default for SortDirection {
-    fn default() -> SortDirection {
-        SortDirection::Asc
-    }
-}
-
 /// Various forms of authentication credentials supported by GitHub
 #[derive(PartialEq, Clone)]
 pub enum Credentials {
@@ -268,6 +381,9 @@ pub enum Credentials {
     /// JWT-based App Installation Token
     /// https://developer.github.com/apps/building-github-apps/authenticating-with-github-apps/
     InstallationToken(InstallationTokenGenerator),
+    /// Several credentials, each request being made with the one with the most rate limit
+    /// budget left, see [CredentialPool](rate_limit/struct.CredentialPool.html)
+    Pool(CredentialPool),
 }
 
 impl fmt::Debug for Credentials {
@@ -292,6 +408,7 @@ impl fmt::Debug for Credentials {
                 .field("installation_id", &generator.installation_id)
                 .field("jwt_credential", &"***")
                 .finish(),
+            Credentials::Pool(pool) => f.debug_tuple("Credentials::Pool").field(pool).finish(),
         }
     }
 }
@@ -324,10 +441,6 @@ impl JWTCredentials {
         })
     }
 
-    fn is_stale(&self) -> bool {
-        self.cache.lock().unwrap().is_stale()
-    }
-
     /// Fetch a valid JWT token, regenerating it if necessary
     pub fn token(&self) -> String {
         let mut expiring = self.cache.lock().unwrap();
@@ -395,15 +508,26 @@ impl ExpiringJWTCredential {
 /// A caching token "generator" which contains JWT credentials.
 ///
 /// The authentication mechanism in the GitHub client library
-/// determines if the token is stale, and if so, uses the contained
-/// JWT credentials to fetch a new installation token.
+/// determines if the token is about to expire, and if so, uses the contained
+/// JWT credentials to fetch a new installation token. Requests finding the
+/// token stale at the same time wait for a single refresh.
 ///
 /// The Mutex<Option> access key is for interior mutability.
 #[derive(Debug, Clone)]
 pub struct InstallationTokenGenerator {
     pub installation_id: u64,
     pub jwt_credential: Box<Credentials>,
-    access_key: Arc<Mutex<Option<String>>>,
+    /// Restrictions of the tokens fetched, see `InstallationTokenGenerator::scoped`
+    pub scope: Option<AccessTokenOptions>,
+    access_key: Arc<Mutex<Option<InstallationToken>>>,
+    /// Held while refreshing the access key
+    refresh: Arc<futures::lock::Mutex<()>>,
+}
+
+#[derive(Debug)]
+struct InstallationToken {
+    token: String,
+    expires_at: time::SystemTime,
 }
 
 impl InstallationTokenGenerator {
@@ -411,39 +535,108 @@ impl InstallationTokenGenerator {
         InstallationTokenGenerator {
             installation_id,
             jwt_credential: Box::new(Credentials::JWT(creds)),
+            scope: None,
             access_key: Arc::new(Mutex::new(None)),
+            refresh: Arc::new(futures::lock::Mutex::new(())),
+        }
+    }
+
+    /// Create a generator of tokens restricted to a subset of the repositories and permissions
+    /// of the installation
+    pub fn scoped(
+        installation_id: u64,
+        creds: JWTCredentials,
+        scope: AccessTokenOptions,
+    ) -> InstallationTokenGenerator {
+        InstallationTokenGenerator {
+            scope: Some(scope),
+            ..InstallationTokenGenerator::new(installation_id, creds)
         }
     }
 
     fn token(&self) -> Option<String> {
-        if let Credentials::JWT(ref creds) = *self.jwt_credential {
-            if creds.is_stale() {
-                return None;
+        let deadline = time::SystemTime::now() + INSTALLATION_TOKEN_REFRESH_MARGIN;
+        self.access_key
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|key| key.expires_at > deadline)
+            .map(|key| key.token.clone())
+    }
+
+    /// Return the installation token, refreshing it with `app` if it's about to expire
+    fn fetch(&self, app: App) -> Future<String> {
+        let generator = self.clone();
+        Box::pin(async move {
+            if let Some(token) = generator.token() {
+                return Ok(token);
             }
+            let _refresh = generator.refresh.lock().await;
+            // another request may have refreshed the token while we waited
+            if let Some(token) = generator.token() {
+                return Ok(token);
+            }
+            debug!("App token is stale, refreshing");
+            let access = match &generator.scope {
+                Some(scope) => {
+                    app.make_scoped_access_token(generator.installation_id, scope)
+                        .await?
+                }
+                None => app.make_access_token(generator.installation_id).await?,
+            };
+            let expires_at = access.expires_at.to_system_time();
+            *generator.access_key.lock().unwrap() = Some(InstallationToken {
+                token: access.token.clone(),
+                expires_at,
+            });
+            Ok(access.token)
+        })
+    }
+
+    /// Discard the installation token if it's still `token`, forcing the next request to
+    /// refresh it
+    fn invalidate(&self, token: &str) {
+        let mut access_key = self.access_key.lock().unwrap();
+        if access_key.as_ref().is_some_and(|key| key.token == token) {
+            *access_key = None;
         }
-        self.access_key.lock().unwrap().clone()
     }
 
     fn jwt(&self) -> &Credentials {
-        &*self.jwt_credential
+        &self.jwt_credential
     }
 }
 
 impl PartialEq for InstallationTokenGenerator {
     fn eq(&self, other: &InstallationTokenGenerator) -> bool {
-        self.installation_id == other.installation_id && self.jwt_credential == other.jwt_credential
+        self.installation_id == other.installation_id
+            && self.jwt_credential == other.jwt_credential
+            && self.scope == other.scope
     }
 }
 
+/// Slot receiving the status and headers of responses, see `Github#with_response`
+type Capture = Arc<Mutex<Option<(StatusCode, HeaderMap)>>>;
+
 /// Entry point interface for interacting with GitHub API
 #[derive(Clone, Debug)]
 pub struct Github {
     host: String,
     agent: String,
-    client: Client,
+    transport: BoxedTransport,
     credentials: Option<Credentials>,
+    retry_policy: RetryPolicy,
+    governor: Option<Governor>,
+    pacer: Option<Pacer>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    capture: Option<Capture>,
+    /// Preconditions sent with GET requests, see `conditional`
+    conditions: Option<Conditions>,
+    page_concurrency: usize,
     #[cfg(feature = "httpcache")]
     http_cache: BoxedHttpCache,
+    #[cfg(feature = "httpcache")]
+    cache_mode: CacheMode,
 }
 
 impl Github {
@@ -469,7 +662,7 @@ impl Github {
                 agent,
                 credentials,
                 http,
-                HttpCache::noop(),
+                <dyn HttpCache>::noop(),
             ))
         }
         #[cfg(not(feature = "httpcache"))]
@@ -494,9 +687,17 @@ impl Github {
         Self {
             host: host.into(),
             agent: agent.into(),
-            client: http,
+            transport: Box::new(ReqwestTransport::new(http)),
             credentials: credentials.into(),
+            retry_policy: RetryPolicy::none(),
+            governor: None,
+            pacer: None,
+            middleware: Vec::new(),
+            capture: None,
+            conditions: None,
+            page_concurrency: 1,
             http_cache,
+            cache_mode: CacheMode::Online,
         }
     }
 
@@ -510,8 +711,15 @@ impl Github {
         Self {
             host: host.into(),
             agent: agent.into(),
-            client: http,
+            transport: Box::new(ReqwestTransport::new(http)),
             credentials: credentials.into(),
+            retry_policy: RetryPolicy::none(),
+            governor: None,
+            pacer: None,
+            middleware: Vec::new(),
+            capture: None,
+            conditions: None,
+            page_concurrency: 1,
         }
     }
 
@@ -522,6 +730,321 @@ impl Github {
         self.credentials = credentials.into();
     }
 
+    /// Set the transport used to send requests, replacing the `reqwest::Client`
+    /// this client was constructed with
+    pub fn set_transport<T>(&mut self, transport: T)
+    where
+        T: Transport + Send + Sync + 'static,
+    {
+        self.transport = Box::new(transport);
+    }
+
+    /// Set the policy used to retry requests failing with transient errors.
+    /// Clients don't retry by default, see [RetryPolicy](retry/struct.RetryPolicy.html)
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Set a governor which paces requests according to the remaining rate limit budget.
+    /// The governor is shared with all clones of this client made afterwards
+    pub fn set_governor<G>(&mut self, governor: G)
+    where
+        G: Into<Option<Governor>>,
+    {
+        self.governor = governor.into();
+    }
+
+    /// Set a pacer which spaces out content creating requests to stay clear of GitHub's
+    /// secondary rate limits. The pacer is shared with all clones of this client made afterwards
+    pub fn set_pacer<P>(&mut self, pacer: P)
+    where
+        P: Into<Option<Pacer>>,
+    {
+        self.pacer = pacer.into();
+    }
+
+    /// Set how the http cache is used, see [CacheMode](http_cache/enum.CacheMode.html)
+    #[cfg(feature = "httpcache")]
+    pub fn set_cache_mode(&mut self, cache_mode: CacheMode) {
+        self.cache_mode = cache_mode;
+    }
+
+    /// Fetch up to `concurrency` pages of streams over paginated listings at once, rather than
+    /// one after another. Items are still yielded in order. Only listings whose first page links
+    /// to the last page by number can be fetched concurrently, others are walked page by page
+    pub fn set_page_concurrency(&mut self, concurrency: usize) {
+        self.page_concurrency = concurrency.max(1);
+    }
+
+    /// Register middleware which sees every request sent and response received by this client
+    /// and all clones of it made afterwards. See [Middleware](middleware/trait.Middleware.html)
+    pub fn add_middleware<M>(&mut self, middleware: M)
+    where
+        M: Middleware + 'static,
+    {
+        self.middleware.push(Arc::new(middleware));
+    }
+
+    /// Make an API call, yielding the entity along with the status and headers of the response
+    ///
+    /// The call is made with a clone of this client passed to `call`, which records the last
+    /// response received through it. Any service method can be called this way, e.g.
+    /// `github.with_response(|github| github.repo("o", "r").get())`. See
+    /// [Response](response/struct.Response.html)
+    ///
+    /// Yields `Error::NoResponse` if the future returned by `call` completes without making a
+    /// request with the client passed to it
+    pub fn with_response<F, T>(&self, call: F) -> Future<Response<T>>
+    where
+        F: FnOnce(&Github) -> Future<T>,
+        T: 'static + Send,
+    {
+        let capture = Arc::new(Mutex::new(None));
+        let mut github = self.clone();
+        github.capture = Some(capture.clone());
+        let entity = call(&github);
+        Box::pin(async move {
+            let entity = entity.await?;
+            let (status, headers) = capture.lock().unwrap().take().ok_or(Error::NoResponse)?;
+            Ok(Response {
+                status,
+                headers,
+                entity,
+            })
+        })
+    }
+
+    /// Make an API call whose GET requests are sent with the given preconditions, yielding
+    /// `Conditional::NotModified` if GitHub reports the entity unchanged
+    ///
+    /// This works the same way as `with_response` and takes precedence over the `httpcache`
+    /// for the requests it makes. See [response](response/index.html) for an example
+    pub fn conditional<F, T>(&self, conditions: Conditions, call: F) -> Future<Conditional<T>>
+    where
+        F: FnOnce(&Github) -> Future<T>,
+        T: 'static + Send,
+    {
+        let mut github = self.clone();
+        github.conditions = Some(conditions);
+        Box::pin(github.with_response(call).then(|result| {
+            future::ready(match result {
+                Ok(response) => Ok(Conditional::Modified(response)),
+                Err(Error::NotModified) => Ok(Conditional::NotModified),
+                Err(err) => Err(err),
+            })
+        }))
+    }
+
+    /// Send a query to the GraphQL API, deserializing the `data` of the response.
+    /// Errors reported by the API are returned as
+    /// [Error::GraphQL](errors/enum.Error.html#variant.GraphQL)
+    pub fn graphql<V, D>(&self, query: &str, variables: V) -> Future<D>
+    where
+        V: Serialize,
+        D: DeserializeOwned + 'static + Send,
+    {
+        let query = graphql::Query { query, variables };
+        Box::pin(
+            self.request_entity::<graphql::Response<D>>(
+                Method::POST,
+                &self.graphql_url(),
+                Some(json!(query)),
+                MediaType::Json,
+                AuthenticationConstraint::Unconstrained,
+            )
+            .and_then(|response| future::ready(response.into_result())),
+        )
+    }
+
+    /// Stream all nodes of a paginated GraphQL connection.
+    ///
+    /// The query is repeated for every page with the `$after` variable set to the end
+    /// cursor of the previous page, so the query must declare `$after: String` and pass
+    /// it to the connection. `connection` extracts the connection from the `data` of each
+    /// response. Variables must serialize to a map
+    pub fn graphql_stream<V, D, I>(
+        &self,
+        query: &str,
+        variables: V,
+        connection: fn(D) -> Connection<I>,
+    ) -> Stream<I>
+    where
+        V: Serialize,
+        D: DeserializeOwned + 'static + Send,
+        I: 'static + Send,
+    {
+        let variables = match serde_json::to_value(variables) {
+            Ok(serde_json::Value::Object(variables)) => variables,
+            Ok(serde_json::Value::Null) => serde_json::Map::new(),
+            Ok(_) => {
+                return Box::pin(stream::once(future::err(Error::Codec(
+                    serde::ser::Error::custom("GraphQL variables must serialize to a map"),
+                ))))
+            }
+            Err(err) => return Box::pin(stream::once(future::err(err.into()))),
+        };
+        let github = self.clone();
+        let query = query.to_owned();
+        let pages = stream::try_unfold(Some(None), move |after: Option<Option<String>>| {
+            let github = github.clone();
+            let query = query.clone();
+            let mut variables = variables.clone();
+            async move {
+                let after = match after {
+                    Some(after) => after,
+                    None => return Ok::<_, Error>(None),
+                };
+                variables.insert("after".into(), after.into());
+                let page = connection(github.graphql(&query, variables).await?);
+                let next = page
+                    .page_info
+                    .next_cursor()
+                    .map(|cursor| Some(cursor.to_owned()));
+                Ok(Some((page.nodes, next)))
+            }
+        });
+        Box::pin(
+            pages
+                .map_ok(|nodes| stream::iter(nodes.into_iter().map(Ok)))
+                .try_flatten(),
+        )
+    }
+
+    /// Send a request to any endpoint of the REST API, deserializing the response.
+    ///
+    /// This is an escape hatch for endpoints hubcaps doesn't provide an interface for yet.
+    /// `uri` is either a path relative to this client's host, like `/repos/{owner}/{repo}`, or
+    /// an absolute url, like the `*_url` members of representations. The request goes through
+    /// the same authentication, retries, caching and error handling as any other, and is sent
+    /// without a body if `body` is `None`, e.g. `None::<&()>`. Use `()` as the response type
+    /// for endpoints responding without content.
+    ///
+    /// ```no_run
+    /// use hubcaps::{AuthenticationConstraint, Github, MediaType};
+    /// use http::Method;
+    /// use serde_json::{json, Value};
+    ///
+    /// # async fn run(github: Github) -> hubcaps::Result<()> {
+    /// let discussion: Value = github
+    ///     .raw_request(
+    ///         Method::POST,
+    ///         "/orgs/rust-lang/teams/core/discussions",
+    ///         Some(&json!({ "title": "Hi", "body": "Hello" })),
+    ///         MediaType::Json,
+    ///         AuthenticationConstraint::Unconstrained,
+    ///     )
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn raw_request<B, D>(
+        &self,
+        method: Method,
+        uri: &str,
+        body: Option<&B>,
+        media_type: MediaType,
+        authentication: AuthenticationConstraint,
+    ) -> Future<D>
+    where
+        B: Serialize + ?Sized,
+        D: DeserializeOwned + 'static + Send,
+    {
+        let body = match body {
+            Some(body) => Some(json!(body)),
+            None => None,
+        };
+        self.request_entity(
+            method,
+            &self.absolute_url(uri),
+            body,
+            media_type,
+            authentication,
+        )
+    }
+
+    /// Stream all items of any paginated list endpoint of the REST API responding with an
+    /// array, following `Link` headers to request subsequent pages. See
+    /// [raw_request](#method.raw_request) for the accepted `uri`s
+    pub fn raw_stream<D>(&self, uri: &str) -> Stream<D>
+    where
+        D: DeserializeOwned + 'static + Send,
+    {
+        unfold(self.clone(), uri, |items| items)
+    }
+
+    /// Like [raw_stream](#method.raw_stream), for endpoints wrapping the items of each page in
+    /// an object, e.g. `{"total_count": 2, "installations": [..]}`. `into_items` extracts the
+    /// items from each page
+    pub fn raw_stream_items<D, I>(&self, uri: &str, into_items: fn(D) -> Vec<I>) -> Stream<I>
+    where
+        D: DeserializeOwned + 'static + Send,
+        I: 'static + Send,
+    {
+        unfold(self.clone(), uri, into_items)
+    }
+
+    /// Stream the items of a paginated list endpoint responding with an array from `cursor`
+    /// onwards, along with the cursor following each item. See
+    /// [Cursor](page/struct.Cursor.html). A cursor for the start of any listing can be made with
+    /// `Cursor::new(uri)`, see [raw_request](#method.raw_request) for the accepted `uri`s
+    pub fn resume_stream<D>(&self, cursor: &Cursor) -> Stream<(Cursor, D)>
+    where
+        D: DeserializeOwned + 'static + Send,
+    {
+        unfold_from(self.clone(), cursor.clone(), |items| items)
+    }
+
+    /// Like [resume_stream](#method.resume_stream), for endpoints wrapping the items of each
+    /// page in an object. `into_items` extracts the items from each page
+    pub fn resume_stream_items<D, I>(
+        &self,
+        cursor: &Cursor,
+        into_items: fn(D) -> Vec<I>,
+    ) -> Stream<(Cursor, I)>
+    where
+        D: DeserializeOwned + 'static + Send,
+        I: 'static + Send,
+    {
+        unfold_from(self.clone(), cursor.clone(), into_items)
+    }
+
+    /// Fetch a single page of any paginated list endpoint of the REST API responding with an
+    /// array. See [Page](page/struct.Page.html) for navigating to other pages and
+    /// [raw_request](#method.raw_request) for the accepted `uri`s
+    pub fn raw_page<D>(&self, uri: &str) -> Future<Page<D>>
+    where
+        D: DeserializeOwned + 'static + Send,
+    {
+        page::get(self, uri, |items| items)
+    }
+
+    /// Like [raw_page](#method.raw_page), for endpoints wrapping the items of each page in an
+    /// object. `into_items` extracts the items from each page
+    pub fn raw_page_items<D, I>(&self, uri: &str, into_items: fn(D) -> Vec<I>) -> Future<Page<I>>
+    where
+        D: DeserializeOwned + 'static + Send,
+        I: 'static + Send,
+    {
+        page::get(self, uri, into_items)
+    }
+
+    /// Resolve a uri relative to this client's host, leaving absolute urls as they are
+    fn absolute_url(&self, uri: &str) -> String {
+        if uri.starts_with("https://") || uri.starts_with("http://") {
+            uri.to_owned()
+        } else {
+            self.host.clone() + uri
+        }
+    }
+
+    /// The GraphQL endpoint, which lives at `/api/graphql` for GitHub Enterprise hosts
+    fn graphql_url(&self) -> String {
+        match self.host.trim_end_matches('/').strip_suffix("/api/v3") {
+            Some(base) => format!("{}/api/graphql", base),
+            None => format!("{}/graphql", self.host.trim_end_matches('/')),
+        }
+    }
+
     pub fn rate_limit(&self) -> RateLimit {
         RateLimit::new(self.clone())
     }
@@ -616,14 +1139,22 @@ impl Github {
         App::new(self.clone())
     }
 
+    /// Return a reference to the OAuth flows of an app, for acquiring user access tokens
+    pub fn oauth<I, S>(&self, client_id: I, client_secret: S) -> OAuth
+    where
+        I: Into<String>,
+        S: Into<Option<String>>,
+    {
+        OAuth::new(self.clone(), client_id, client_secret)
+    }
+
     fn credentials(&self, authentication: AuthenticationConstraint) -> Option<&Credentials> {
         match (authentication, self.credentials.as_ref()) {
             (AuthenticationConstraint::Unconstrained, creds) => creds,
             (AuthenticationConstraint::JWT, creds @ Some(&Credentials::JWT(_))) => creds,
-            (
-                AuthenticationConstraint::JWT,
-                Some(&Credentials::InstallationToken(ref apptoken)),
-            ) => Some(apptoken.jwt()),
+            (AuthenticationConstraint::JWT, Some(Credentials::InstallationToken(apptoken))) => {
+                Some(apptoken.jwt())
+            }
             (AuthenticationConstraint::JWT, creds) => {
                 error!(
                     "Request needs JWT authentication but only {:?} available",
@@ -642,7 +1173,7 @@ impl Github {
         let parsed_url = uri.parse::<Url>();
 
         match self.credentials(authentication) {
-            Some(&Credentials::Client(ref id, ref secret)) => Box::pin(future::ready(
+            Some(Credentials::Client(id, secret)) => Box::pin(future::ready(
                 parsed_url
                     .map(|mut u| {
                         u.query_pairs_mut()
@@ -652,46 +1183,68 @@ impl Github {
                     })
                     .map_err(Error::from),
             )),
-            Some(&Credentials::Token(ref token)) => {
+            Some(Credentials::Token(token)) => {
                 let auth = format!("token {}", token);
                 Box::pin(future::ready(
                     parsed_url.map(|u| (u, Some(auth))).map_err(Error::from),
                 ))
             }
-            Some(&Credentials::JWT(ref jwt)) => {
+            Some(Credentials::JWT(jwt)) => {
                 let auth = format!("Bearer {}", jwt.token());
                 Box::pin(future::ready(
                     parsed_url.map(|u| (u, Some(auth))).map_err(Error::from),
                 ))
             }
-            Some(&Credentials::InstallationToken(ref apptoken)) => {
-                if let Some(token) = apptoken.token() {
+            Some(Credentials::InstallationToken(apptoken)) => {
+                Box::pin(apptoken.fetch(self.app()).and_then(move |token| {
                     let auth = format!("token {}", token);
-                    Box::pin(future::ready(
-                        parsed_url.map(|u| (u, Some(auth))).map_err(Error::from),
-                    ))
-                } else {
-                    debug!("App token is stale, refreshing");
-                    let token_ref = apptoken.access_key.clone();
-                    Box::pin(
-                        self.app()
-                            .make_access_token(apptoken.installation_id)
-                            .and_then(move |token| {
-                                let auth = format!("token {}", &token.token);
-                                *token_ref.lock().unwrap() = Some(token.token);
-                                future::ready(
-                                    parsed_url.map(|u| (u, Some(auth))).map_err(Error::from),
-                                )
-                            }),
-                    )
-                }
+                    future::ready(parsed_url.map(|u| (u, Some(auth))).map_err(Error::from))
+                }))
             }
-            None => Box::pin(future::ready(
+            Some(Credentials::Pool(_)) | None => Box::pin(future::ready(
                 parsed_url.map(|u| (u, None)).map_err(Error::from),
             )),
         }
     }
 
+    /// Pick the member of a credential pool to make the next attempt at a request with,
+    /// returning the pool and the member's index in it along with a client using its credentials
+    fn pool_member(
+        &self,
+        resource: Resource,
+        authentication: AuthenticationConstraint,
+    ) -> Result<Option<(CredentialPool, usize, Github)>> {
+        let pool = match (authentication, &self.credentials) {
+            (AuthenticationConstraint::Unconstrained, Some(Credentials::Pool(pool))) => pool,
+            _ => return Ok(None),
+        };
+        Ok(pool.acquire(resource)?.map(|(index, credentials)| {
+            let mut member = self.clone();
+            member.credentials = Some(credentials);
+            (pool.clone(), index, member)
+        }))
+    }
+
+    /// Resolve installation token credentials to the token to make the next attempt at a
+    /// request with, returning the generator and the token along with a client using it
+    fn installation_token(
+        &self,
+        authentication: AuthenticationConstraint,
+    ) -> Future<Option<(InstallationTokenGenerator, String, Github)>> {
+        let generator = match (authentication, &self.credentials) {
+            (
+                AuthenticationConstraint::Unconstrained,
+                Some(Credentials::InstallationToken(generator)),
+            ) => generator.clone(),
+            _ => return Box::pin(future::ok(None)),
+        };
+        let mut client = self.clone();
+        Box::pin(generator.fetch(self.app()).map_ok(move |token| {
+            client.credentials = Some(Credentials::Token(token.clone()));
+            Some((generator, token, client))
+        }))
+    }
+
     fn request<Out>(
         &self,
         method: Method,
@@ -703,6 +1256,179 @@ impl Github {
     where
         Out: DeserializeOwned + 'static + Send,
     {
+        let instance = self.clone();
+        let uri = uri.to_string();
+        let call_method = method.clone();
+        let call_uri = uri.clone();
+        let call = async move {
+            let started = Instant::now();
+            let policy = &instance.retry_policy;
+            let resource = Resource::from_uri(&uri);
+            let mut attempts = 1;
+            let mut awaited_reset = false;
+            let mut refreshed_token = false;
+            let mut failovers = 0;
+            #[cfg(feature = "httpcache")]
+            {
+                if instance.cache_mode == CacheMode::Offline {
+                    return if method == Method::GET {
+                        instance
+                            .stale_response(&uri, DISCONNECTED_OPERATION)
+                            .map_err(|_| Error::Offline)
+                    } else {
+                        Err(Error::Offline)
+                    };
+                }
+            }
+            loop {
+                if let Some(governor) = &instance.governor {
+                    if let Some(delay) = governor.acquire(resource) {
+                        debug!("Waiting {:?} for {:?} rate limit budget", delay, resource);
+                        delay_for(delay).await;
+                    }
+                }
+                if let Some(pacer) = instance.pacer.as_ref().filter(|p| p.paces(&method)) {
+                    if let Some(delay) = pacer.acquire() {
+                        debug!("Pacing {} {} by {:?}", method, uri, delay);
+                        delay_for(delay).await;
+                    }
+                }
+                let member = instance.pool_member(resource, authentication)?;
+                let sender = member.as_ref().map_or(&instance, |(_, _, member)| member);
+                let installation = sender.installation_token(authentication).await?;
+                let response = installation
+                    .as_ref()
+                    .map_or(sender, |(_, _, client)| client)
+                    .send(
+                        method.clone(),
+                        &uri,
+                        body.clone(),
+                        media_type,
+                        authentication,
+                    )
+                    .await;
+                if let (Some(governor), Ok((_, headers, _))) = (&instance.governor, &response) {
+                    governor.update(&uri, headers);
+                }
+                if let (Some((pool, index, _)), Ok((_, headers, _))) = (&member, &response) {
+                    pool.update(*index, &uri, headers);
+                }
+                let delay = match response {
+                    Ok((status, _, _))
+                        if status == StatusCode::UNAUTHORIZED
+                            && installation.is_some()
+                            && !refreshed_token =>
+                    {
+                        if let Some((generator, token, _)) = &installation {
+                            debug!("App token was rejected, refreshing");
+                            generator.invalidate(token);
+                        }
+                        refreshed_token = true;
+                        continue;
+                    }
+                    Ok((status, ref headers, _))
+                        if member
+                            .as_ref()
+                            .is_some_and(|(pool, _, _)| failovers < pool.len())
+                            && is_rate_limited(status, headers) =>
+                    {
+                        if let Some((pool, index, _)) = &member {
+                            debug!("Pool member {} exhausted its rate limit", index);
+                            pool.exhaust(*index, &uri, headers);
+                        }
+                        // the pool fails over to another member, or runs dry, once per member
+                        failovers += 1;
+                        continue;
+                    }
+                    Ok((status, ref headers, _))
+                        if instance.governor.is_some()
+                            && !awaited_reset
+                            && is_rate_limited(status, headers) =>
+                    {
+                        // the governor makes the next attempt wait for the reset
+                        awaited_reset = true;
+                        continue;
+                    }
+                    Ok((status, ref headers, _))
+                        if attempts < policy.max_attempts()
+                            && policy.is_retryable_status(&method, status) =>
+                    {
+                        debug!("{} {} responded with {}", method, uri, status);
+                        policy.status_backoff(attempts, headers)
+                    }
+                    Err(ref err)
+                        if attempts < policy.max_attempts()
+                            && policy.is_retryable_error(&method, err) =>
+                    {
+                        debug!("{} {} failed: {}", method, uri, err);
+                        policy.backoff(attempts)
+                    }
+                    Ok((status, headers, response_body)) => {
+                        instrument::record_response(status, &headers, started, attempts);
+                        #[cfg(feature = "httpcache")]
+                        {
+                            if status.is_server_error() && instance.serves_stale(&method) {
+                                if let Ok(stale) =
+                                    instance.stale_response(&uri, REVALIDATION_FAILED)
+                                {
+                                    return Ok(stale);
+                                }
+                            }
+                        }
+                        if let Some(capture) = &instance.capture {
+                            *capture.lock().unwrap() = Some((status, headers.clone()));
+                        }
+                        #[cfg(feature = "httpcache")]
+                        {
+                            if status.is_success()
+                                && method != Method::GET
+                                && method != Method::HEAD
+                            {
+                                instance.invalidate_cache(&method, &uri);
+                            }
+                        }
+                        if status == StatusCode::NOT_MODIFIED && instance.conditions.is_some() {
+                            return Err(Error::NotModified);
+                        }
+                        return instance.response(&method, &uri, status, &headers, response_body);
+                    }
+                    Err(err) => {
+                        instrument::record_error(&err, started, attempts);
+                        #[cfg(feature = "httpcache")]
+                        {
+                            if instance.serves_stale(&method) {
+                                if let Ok(stale) =
+                                    instance.stale_response(&uri, REVALIDATION_FAILED)
+                                {
+                                    return Ok(stale);
+                                }
+                            }
+                        }
+                        return Err(err);
+                    }
+                };
+                debug!(
+                    "Retrying attempt {} of {} in {:?}",
+                    attempts + 1,
+                    policy.max_attempts(),
+                    delay
+                );
+                delay_for(delay).await;
+                attempts += 1;
+            }
+        };
+        Box::pin(instrument::call(&call_method, &call_uri, call))
+    }
+
+    /// Make a single attempt at sending a request, yielding the response status, headers and body
+    fn send(
+        &self,
+        method: Method,
+        uri: &str,
+        body: Option<Vec<u8>>,
+        media_type: MediaType,
+        authentication: AuthenticationConstraint,
+    ) -> Future<(StatusCode, HeaderMap, Vec<u8>)> {
         let url_and_auth = self.url_and_auth(uri, authentication);
 
         let instance = self.clone();
@@ -711,137 +1437,250 @@ impl Github {
         let response = url_and_auth
             .map_err(Error::from)
             .and_then(move |(url, auth)| {
-                #[cfg(not(feature = "httpcache"))]
-                let mut req = instance.client.request(method, url);
-
-                #[cfg(feature = "httpcache")]
-                let mut req = {
-                    let mut req = instance.client.request(method.clone(), url);
-                    if method == Method::GET {
+                let mut req = http::Request::builder()
+                    .method(method.clone())
+                    .uri(url.as_str())
+                    .header(USER_AGENT, &*instance.agent)
+                    .header(
+                        ACCEPT,
+                        &*format!("{}", qitem::<Mime>(From::from(media_type))),
+                    );
+
+                match &instance.conditions {
+                    Some(conditions) if method == Method::GET => {
+                        if let Some(etag) = &conditions.etag {
+                            req = req.header(IF_NONE_MATCH, &**etag);
+                        }
+                        if let Some(last_modified) = &conditions.last_modified {
+                            req = req.header(IF_MODIFIED_SINCE, &**last_modified);
+                        }
+                    }
+                    #[cfg(feature = "httpcache")]
+                    None if method == Method::GET => {
                         if let Ok(etag) = instance.http_cache.lookup_etag(&uri2) {
                             req = req.header(IF_NONE_MATCH, etag);
                         }
                     }
-                    req
-                };
-
-                req = req.header(USER_AGENT, &*instance.agent);
-                req = req.header(
-                    ACCEPT,
-                    &*format!("{}", qitem::<Mime>(From::from(media_type))),
-                );
+                    _ => (),
+                }
 
                 if let Some(auth_str) = auth {
                     req = req.header(AUTHORIZATION, &*auth_str);
                 }
 
                 trace!("Body: {:?}", &body);
-                if let Some(body) = body {
-                    req = req.body(Body::from(body));
+                let req = req.body(body).map_err(Error::from).and_then(|mut req| {
+                    for middleware in &instance.middleware {
+                        middleware.on_request(&mut req)?;
+                    }
+                    Ok(req)
+                });
+                match req {
+                    Ok(req) => {
+                        let method = req.method().clone();
+                        let uri = req.uri().clone();
+                        let middleware = instance.middleware.clone();
+                        let response: Future<http::Response<Vec<u8>>> =
+                            Box::pin(instance.transport.send(req).and_then(
+                                |response| async move {
+                                    for middleware in middleware.iter().rev() {
+                                        middleware.on_response(&method, &uri, &response)?;
+                                    }
+                                    Ok(response)
+                                },
+                            ));
+                        response
+                    }
+                    Err(err) => Box::pin(future::err(err)),
                 }
-                debug!("Request: {:?}", &req);
-                req.send().map_err(Error::from)
             });
 
-        #[cfg(feature = "httpcache")]
-        let instance2 = self.clone();
+        Box::pin(response.map_ok(|response| {
+            let (parts, body) = response.into_parts();
+            (parts.status, parts.headers, body)
+        }))
+    }
 
+    /// Returns true if a failed `method` request should be served from the cache
+    #[cfg(feature = "httpcache")]
+    fn serves_stale(&self, method: &Method) -> bool {
+        self.cache_mode == CacheMode::StaleIfError && *method == Method::GET
+    }
+
+    /// Invalidate the cached responses a successful mutating request to `uri` made stale,
+    /// those for the resource and any beneath it, and the listing of its parent collection
+    #[cfg(feature = "httpcache")]
+    fn invalidate_cache(&self, method: &Method, uri: &str) {
+        let uri = uri
+            .split(['?', '#'])
+            .next()
+            .unwrap_or(uri)
+            .trim_end_matches('/');
+        // creating a member of a collection leaves its other members untouched
+        let mut result = if *method == Method::POST {
+            self.http_cache.invalidate(uri)
+        } else {
+            self.http_cache.purge_prefix(uri)
+        };
+        let path = uri
+            .find("://")
+            .and_then(|scheme| uri[scheme + 3..].find('/').map(|path| scheme + 3 + path));
+        if let (Some(path), Some(parent)) = (path, uri.rfind('/')) {
+            if parent > path {
+                result = result.and(self.http_cache.invalidate(&uri[..parent]));
+            }
+        }
+        if let Err(e) = result {
+            // failing to invalidate isn't fatal, so just log & swallow the error
+            debug!("Failed to invalidate cached responses: {}", e);
+        }
+    }
+
+    /// Read the response to a GET request to `uri` from the cache, using `link` if the request
+    /// was answered with one
+    #[cfg(feature = "httpcache")]
+    fn cached_response<Out>(&self, uri: &str, link: Option<Link>) -> Result<(Option<Link>, Out)>
+    where
+        Out: DeserializeOwned,
+    {
+        self.http_cache.lookup_body(uri).and_then(|body| {
+            serde_json::from_str::<Out>(&body)
+                .map_err(Error::from)
+                .and_then(|out| {
+                    let link = match link {
+                        Some(link) => Ok(Some(link)),
+                        None => self.http_cache.lookup_next_link(uri).map(|next_link| {
+                            next_link.map(|next| {
+                                let next = LinkValue::new(next).push_rel(RelationType::Next);
+                                Link::new(vec![next])
+                            })
+                        }),
+                    };
+                    link.map(|link| (link, out))
+                })
+        })
+    }
+
+    /// Serve a GET request to `uri` from the cache without revalidating it, marking the
+    /// response captured by `with_response` stale with the given `Warning`
+    #[cfg(feature = "httpcache")]
+    fn stale_response<Out>(&self, uri: &str, warning: &'static str) -> Result<(Option<Link>, Out)>
+    where
+        Out: DeserializeOwned,
+    {
+        let (link, out) = self.cached_response(uri, None)?;
+        debug!("Serving stale response for {}", uri);
+        if let Some(capture) = &self.capture {
+            let mut headers = HeaderMap::new();
+            headers.insert(WARNING, HeaderValue::from_static(warning));
+            if let Some(link) = link
+                .as_ref()
+                .and_then(|link| HeaderValue::from_str(&link.to_string()).ok())
+            {
+                headers.insert(LINK, link);
+            }
+            *capture.lock().unwrap() = Some((StatusCode::OK, headers));
+        }
+        Ok((link, out))
+    }
+
+    /// Interpret a response, yielding either its deserialized body or an error
+    fn response<Out>(
+        &self,
+        method: &Method,
+        uri: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        response_body: Vec<u8>,
+    ) -> Result<(Option<Link>, Out)>
+    where
+        Out: DeserializeOwned,
+    {
+        #[cfg(not(feature = "httpcache"))]
+        let (remaining, reset) = get_header_values(headers);
+        #[cfg(not(feature = "httpcache"))]
+        let _ = method;
         #[cfg(feature = "httpcache")]
-        let uri3 = uri.to_string();
-        Box::pin(response.and_then(move |response| {
-            #[cfg(not(feature = "httpcache"))]
-            let (remaining, reset) = get_header_values(response.headers());
+        let (remaining, reset, etag) = get_header_values(headers);
+
+        let link = headers
+            .get(LINK)
+            .and_then(|l| l.to_str().ok())
+            .and_then(|l| l.parse().ok());
+
+        if status.is_success() {
+            debug!(
+                "response payload {}",
+                String::from_utf8_lossy(&response_body)
+            );
+            // only GET responses represent the entity at the uri, a mutation's response
+            // mustn't replace what was invalidated for it
             #[cfg(feature = "httpcache")]
-            let (remaining, reset, etag) = get_header_values(response.headers());
-
-            let status = response.status();
-            let link = response
-                .headers()
-                .get(LINK)
-                .and_then(|l| l.to_str().ok())
-                .and_then(|l| l.parse().ok());
-
-            Box::pin(
-                response
-                    .bytes()
-                    .map_err(Error::from)
-                    .and_then(move |response_body| async move {
-                        if status.is_success() {
-                            debug!(
-                                "response payload {}",
-                                String::from_utf8_lossy(&response_body)
-                            );
-                            #[cfg(feature = "httpcache")]
-                            {
-                                if let Some(etag) = etag {
-                                    let next_link = link.as_ref().and_then(|l| next_link(&l));
-                                    if let Err(e) = instance2.http_cache.cache_response(
-                                        &uri3,
-                                        &response_body,
-                                        &etag,
-                                        &next_link,
-                                    ) {
-                                        // failing to cache isn't fatal, so just log & swallow the error
-                                        debug!("Failed to cache body & etag: {}", e);
-                                    }
-                                }
-                            }
-                            let parsed_response = if status == StatusCode::NO_CONTENT { serde_json::from_str("null") } else { serde_json::from_slice::<Out>(&response_body) };
-                            parsed_response
-                                .map(|out| (link, out))
-                                .map_err(Error::Codec)
-                        } else if status == StatusCode::NOT_MODIFIED {
-                            // only supported case is when client provides if-none-match
-                            // header when cargo builds with --cfg feature="httpcache"
-                            #[cfg(feature = "httpcache")]
-                            {
-                                instance2
-                                    .http_cache
-                                    .lookup_body(&uri3)
-                                    .map_err(Error::from)
-                                    .and_then(|body| {
-                                        serde_json::from_str::<Out>(&body)
-                                            .map_err(Error::from)
-                                            .and_then(|out| {
-                                                let link = match link {
-                                                    Some(link) => Ok(Some(link)),
-                                                    None => instance2
-                                                        .http_cache
-                                                        .lookup_next_link(&uri3)
-                                                        .map(|next_link| next_link.map(|next| {
-                                                            let next = LinkValue::new(next).push_rel(RelationType::Next);
-                                                            Link::new(vec![next])
-                                                        }))
-                                                };
-                                                link.map(|link| (link, out))
-                                            })
-                                    })
-                            }
-                            #[cfg(not(feature = "httpcache"))]
-                            {
-                                unreachable!("this should not be reachable without the httpcache feature enabled")
-                            }
-                        } else {
-                            let error = match (remaining, reset) {
-                                (Some(remaining), Some(reset)) if remaining == 0 => {
-                                    let now = SystemTime::now()
-                                        .duration_since(UNIX_EPOCH)
-                                        .unwrap()
-                                        .as_secs();
-                                    Error::RateLimit {
-                                        reset: Duration::from_secs(u64::from(reset) - now),
-                                    }
-                                }
-                                _ => Error::Fault {
-                                    code: status,
-                                    error: serde_json::from_slice(&response_body)?,
-                                },
-                            };
-                            Err(error)
+            {
+                if let (Some(etag), &Method::GET) = (etag, method) {
+                    let next_link = link.as_ref().and_then(next_link);
+                    if let Err(e) =
+                        self.http_cache
+                            .cache_response(uri, &response_body, &etag, &next_link)
+                    {
+                        // failing to cache isn't fatal, so just log & swallow the error
+                        debug!("Failed to cache body & etag: {}", e);
+                    }
+                }
+            }
+            let parsed_response = if status == StatusCode::NO_CONTENT {
+                serde_json::from_str("null")
+            } else {
+                serde_json::from_slice::<Out>(&response_body)
+            };
+            parsed_response.map(|out| (link, out)).map_err(Error::Codec)
+        } else if status == StatusCode::NOT_MODIFIED {
+            // the http cache sends if-none-match itself when cargo builds with
+            // --cfg feature="httpcache", otherwise a middleware or transport did, possibly
+            // for an entity the cache doesn't hold
+            #[cfg(feature = "httpcache")]
+            {
+                match self.cached_response(uri, link) {
+                    Err(Error::IO(ref err)) if err.kind() == std::io::ErrorKind::NotFound => {
+                        Err(Error::NotModified)
+                    }
+                    result => result,
+                }
+            }
+            #[cfg(not(feature = "httpcache"))]
+            {
+                let _ = (uri, link);
+                Err(Error::NotModified)
+            }
+        } else {
+            let error = match (remaining, reset) {
+                (Some(0), Some(reset)) => {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs();
+                    Error::RateLimit {
+                        reset: Duration::from_secs(u64::from(reset).saturating_sub(now)),
+                    }
+                }
+                _ => {
+                    let error: ClientError = serde_json::from_slice(&response_body)?;
+                    if is_secondary_rate_limited(status, headers, &error) {
+                        Error::SecondaryRateLimit {
+                            retry_after: retry_after(headers)
+                                .unwrap_or(SECONDARY_RATE_LIMIT_RETRY_AFTER),
+                            error,
                         }
-                    }),
-            )
-        }))
+                    } else {
+                        Error::Fault {
+                            code: status,
+                            error,
+                        }
+                    }
+                }
+            };
+            Err(error)
+        }
     }
 
     fn request_entity<D>(
@@ -885,7 +1724,14 @@ impl Github {
     where
         D: DeserializeOwned + 'static + Send,
     {
-        unfold(self.clone(), self.get_pages(uri), |x| x)
+        unfold(self.clone(), uri, |x| x)
+    }
+
+    fn get_page<D>(&self, uri: &str) -> Future<Page<D>>
+    where
+        D: DeserializeOwned + 'static + Send,
+    {
+        page::get(self, uri, |items| items)
     }
 
     fn get_pages<D>(&self, uri: &str) -> Future<(Option<Link>, D)>
@@ -894,7 +1740,7 @@ impl Github {
     {
         self.request(
             Method::GET,
-            &(self.host.clone() + uri),
+            &self.absolute_url(uri),
             None,
             MediaType::Json,
             AuthenticationConstraint::Unconstrained,
@@ -1080,6 +1926,24 @@ fn get_header_values(headers: &HeaderMap<HeaderValue>) -> HeaderValues {
     (remaining, reset)
 }
 
+/// Returns true if a response failed because the rate limit budget was exhausted
+fn is_rate_limited(status: StatusCode, headers: &HeaderMap) -> bool {
+    (status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS)
+        && headers
+            .get(X_RATELIMIT_REMAINING)
+            .and_then(|value| value.to_str().ok())
+            == Some("0")
+}
+
+/// Returns true if a response failed because GitHub's secondary rate limits were triggered
+fn is_secondary_rate_limited(status: StatusCode, headers: &HeaderMap, error: &ClientError) -> bool {
+    (status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS)
+        && (headers.contains_key(RETRY_AFTER) || {
+            let message = error.message.to_lowercase();
+            message.contains("secondary rate limit") || message.contains("abuse")
+        })
+}
+
 fn next_link(l: &Link) -> Option<String> {
     l.values().iter().find_map(|value| {
         value.rel().and_then(|rels| {
@@ -1092,45 +1956,106 @@ fn next_link(l: &Link) -> Option<String> {
     })
 }
 
-/// "unfold" paginated results of a list of github entities
-fn unfold<D, I>(
+/// "unfold" paginated results of a list of github entities, starting with the page at `uri`
+fn unfold<D, I>(github: Github, uri: &str, into_items: fn(D) -> Vec<I>) -> Stream<I>
+where
+    D: DeserializeOwned + 'static + Send,
+    I: 'static + Send,
+{
+    Box::pin(unfold_from(github, Cursor::new(uri), into_items).map_ok(|(_, item)| item))
+}
+
+/// "unfold" paginated results of a list of github entities, starting at `cursor`, along with
+/// the cursor following each of them
+fn unfold_from<D, I>(
     github: Github,
-    first: Future<(Option<Link>, D)>,
+    cursor: Cursor,
     into_items: fn(D) -> Vec<I>,
-) -> Stream<I>
+) -> Stream<(Cursor, I)>
 where
     D: DeserializeOwned + 'static + Send,
     I: 'static + Send,
 {
+    let uri = github.absolute_url(&cursor.url);
+    let span = instrument::stream(&uri);
+    let number = page::page_number(&uri).unwrap_or(1);
+    let first = {
+        let github = github.clone();
+        let uri = uri.clone();
+        instrument::page(&span, number, async move { github.get_pages(&uri).await })
+    };
     Box::pin(
         first
             .map_ok(move |(link, payload)| {
-                let mut items = into_items(payload);
+                let items = Cursor::items(&uri, cursor.offset, into_items(payload));
+                if github.page_concurrency > 1 {
+                    if let Some(urls) = link.as_ref().and_then(page::following) {
+                        return prefetch(github, span, urls, number, into_items, items);
+                    }
+                }
+                let mut items = items;
                 items.reverse();
-                stream::try_unfold(
-                    (github, link, items),
-                    move |(github, link, mut items)| async move {
-                        match items.pop() {
-                            Some(item) => Ok(Some((item, (github, link, items)))),
-                            None => match link.and_then(|l| next_link(&l)) {
+                let pages = stream::try_unfold(
+                    (github, link, items, number),
+                    move |(github, link, mut items, number)| {
+                        let span = span.clone();
+                        async move {
+                            if let Some(item) = items.pop() {
+                                return Ok(Some((item, (github, link, items, number))));
+                            }
+                            match link.and_then(|l| next_link(&l)) {
                                 Some(url) => {
-                                    let url = Url::from_str(&url).unwrap();
-                                    let (link, payload) = github.get_pages_url(&url).await?;
-                                    let mut items = into_items(payload);
-                                    let item = items.remove(0);
+                                    let (link, payload) =
+                                        instrument::page(&span, number + 1, async {
+                                            github.get_pages_url(&Url::from_str(&url)?).await
+                                        })
+                                        .await?;
+                                    let mut items = Cursor::items(&url, 0, into_items(payload));
                                     items.reverse();
-                                    Ok(Some((item, (github, link, items))))
+                                    Ok(items
+                                        .pop()
+                                        .map(|item| (item, (github, link, items, number + 1))))
                                 }
                                 None => Ok(None),
-                            },
+                            }
                         }
                     },
-                )
+                );
+                Box::pin(pages) as Stream<(Cursor, I)>
             })
             .try_flatten_stream(),
     )
 }
 
+/// Stream the `items` of the page numbered `number` of a listing followed by those of the
+/// pages at `urls`, fetching up to the client's page concurrency of them at once
+fn prefetch<D, I>(
+    github: Github,
+    span: instrument::Span,
+    urls: Vec<String>,
+    number: u32,
+    into_items: fn(D) -> Vec<I>,
+    items: Vec<(Cursor, I)>,
+) -> Stream<(Cursor, I)>
+where
+    D: DeserializeOwned + 'static + Send,
+    I: 'static + Send,
+{
+    let concurrency = github.page_concurrency;
+    let pages = stream::iter(urls.into_iter().zip(number + 1..))
+        .map(move |(url, number)| {
+            let github = github.clone();
+            instrument::page(&span, number, async move {
+                let (_, payload) = github.get_pages::<D>(&url).await?;
+                let items = Cursor::items(&url, 0, into_items(payload));
+                Ok::<_, Error>(stream::iter(items.into_iter().map(Ok)))
+            })
+        })
+        .buffered(concurrency)
+        .try_flatten();
+    Box::pin(stream::iter(items.into_iter().map(Ok)).chain(pages))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1150,6 +2075,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn secondary_rate_limits() {
+        let error = |message: &str| ClientError {
+            message: message.into(),
+            errors: None,
+            documentation_url: None,
+        };
+        let mut retry_after = HeaderMap::new();
+        retry_after.insert(RETRY_AFTER, HeaderValue::from_static("30"));
+
+        assert!(is_secondary_rate_limited(
+            StatusCode::FORBIDDEN,
+            &retry_after,
+            &error("Forbidden")
+        ));
+        assert!(is_secondary_rate_limited(
+            StatusCode::FORBIDDEN,
+            &HeaderMap::new(),
+            &error("You have exceeded a secondary rate limit. Please wait a few minutes before you try again.")
+        ));
+        assert!(!is_secondary_rate_limited(
+            StatusCode::FORBIDDEN,
+            &HeaderMap::new(),
+            &error("Resource not accessible by integration")
+        ));
+        assert!(!is_secondary_rate_limited(
+            StatusCode::NOT_FOUND,
+            &retry_after,
+            &error("Not Found")
+        ));
+    }
+
     #[test]
     fn default_sort_direction() {
         let default: SortDirection = Default::default();
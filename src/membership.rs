@@ -2,7 +2,7 @@
 use serde::Deserialize;
 
 use crate::users::User;
-use crate::{Github, Stream};
+use crate::{Github, Stream, Timestamp};
 
 /// Provides access to membership operations available for an individual organization
 pub struct OrgMembership {
@@ -38,7 +38,7 @@ pub struct Invitation {
     pub login: Option<String>,
     pub email: Option<String>,
     pub role: InvitedRole,
-    pub created_at: String, // TODO: change to `DateTime`?
+    pub created_at: Timestamp,
     pub inviter: User,
     pub team_count: Option<u64>,
 }
@@ -0,0 +1,168 @@
+//! Request and response middleware
+//!
+//! [Middleware](trait.Middleware.html) registered with
+//! [Github#add_middleware](../struct.Github.html#method.add_middleware) sees every request a
+//! client sends, regardless of the service or verb used to make it. It may modify requests
+//! before they are handed to the transport, for instance to add headers or sign them, and
+//! observe the raw responses before they are interpreted, for instance to log them.
+//!
+//! ```no_run
+//! use hubcaps::{middleware::SetHeader, Github};
+//!
+//! # fn main() -> hubcaps::Result<()> {
+//! let mut github = Github::new("user-agent-name", None)?;
+//! github.add_middleware(SetHeader::new("X-GitHub-Api-Version", "2022-11-28")?);
+//! # Ok(())
+//! # }
+//! ```
+use std::convert::TryFrom;
+use std::fmt::Debug;
+
+use http::header::{HeaderName, HeaderValue};
+use http::{Method, Request, Response, Uri};
+
+use crate::Result;
+
+/// Hooks run for every request a `Github` client sends
+///
+/// Request hooks run in the order middleware was registered, response hooks in the reverse
+/// order. Both run once per attempt, so a request which is retried passes through them again.
+/// Returning an error from either hook fails the call with that error.
+pub trait Middleware: Debug + Send + Sync {
+    /// Inspect or modify a request before it is sent
+    fn on_request(&self, _request: &mut Request<Option<Vec<u8>>>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Observe the response to a request before it is interpreted
+    fn on_response(
+        &self,
+        _method: &Method,
+        _uri: &Uri,
+        _response: &Response<Vec<u8>>,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Middleware which sets a header on every request, replacing any value set by the client
+#[derive(Clone, Debug)]
+pub struct SetHeader {
+    name: HeaderName,
+    value: HeaderValue,
+}
+
+impl SetHeader {
+    pub fn new(name: &str, value: &str) -> Result<Self> {
+        Ok(SetHeader {
+            name: HeaderName::try_from(name).map_err(http::Error::from)?,
+            value: HeaderValue::try_from(value).map_err(http::Error::from)?,
+        })
+    }
+}
+
+impl Middleware for SetHeader {
+    fn on_request(&self, request: &mut Request<Option<Vec<u8>>>) -> Result<()> {
+        request
+            .headers_mut()
+            .insert(self.name.clone(), self.value.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{github, FakeTransport};
+    use crate::Error;
+    use http::StatusCode;
+    use std::sync::{Arc, Mutex};
+
+    /// Middleware tagging requests and recording the statuses it observed
+    #[derive(Debug, Default)]
+    struct Audit {
+        statuses: Mutex<Vec<StatusCode>>,
+    }
+
+    impl Middleware for Arc<Audit> {
+        fn on_request(&self, request: &mut http::Request<Option<Vec<u8>>>) -> Result<()> {
+            if request.uri().path() == "/forbidden" {
+                return Err(Error::IO(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    "forbidden",
+                )));
+            }
+            request
+                .headers_mut()
+                .insert("x-tenant", HeaderValue::from_static("acme"));
+            Ok(())
+        }
+
+        fn on_response(
+            &self,
+            _method: &Method,
+            _uri: &http::Uri,
+            response: &http::Response<Vec<u8>>,
+        ) -> Result<()> {
+            self.statuses.lock().unwrap().push(response.status());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn applies_middleware() {
+        let transport = FakeTransport::new(vec![(201, "{}")]);
+        let audit = Arc::new(Audit::default());
+        let mut github = github(&transport);
+        github.add_middleware(SetHeader::new("x-github-api-version", "2022-11-28").unwrap());
+        github.add_middleware(audit.clone());
+
+        github
+            .post::<serde_json::Value>("/user/repos", b"{}".to_vec())
+            .await
+            .unwrap();
+        assert!(github.get::<serde_json::Value>("/forbidden").await.is_err());
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].2["x-tenant"], "acme");
+        assert_eq!(requests[0].2["x-github-api-version"], "2022-11-28");
+        assert_eq!(*audit.statuses.lock().unwrap(), vec![StatusCode::CREATED]);
+    }
+
+    #[tokio::test]
+    #[cfg(not(feature = "httpcache"))]
+    async fn middleware_preconditions_yield_not_modified() {
+        let transport = FakeTransport::new(vec![(304, "")]);
+        let mut github = github(&transport);
+        github.add_middleware(SetHeader::new("if-none-match", "\"abc\"").unwrap());
+
+        match github.get::<serde_json::Value>("/user").await {
+            Err(Error::NotModified) => (),
+            other => panic!("expected not modified, got {:?}", other),
+        }
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests[0].2["if-none-match"], "\"abc\"");
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "httpcache")]
+    async fn middleware_preconditions_yield_not_modified_for_uncached_entities() {
+        let transport = FakeTransport::new(vec![(304, "")]);
+        let cache = crate::http_cache::InMemoryCache::new(10, 1024);
+        let mut github = crate::Github::custom(
+            crate::DEFAULT_HOST,
+            "agent",
+            None,
+            reqwest::Client::new(),
+            Box::new(cache),
+        );
+        github.set_transport(transport);
+        github.add_middleware(SetHeader::new("if-none-match", "\"abc\"").unwrap());
+
+        match github.get::<serde_json::Value>("/user").await {
+            Err(Error::NotModified) => (),
+            other => panic!("expected not modified, got {:?}", other),
+        }
+    }
+}
@@ -7,6 +7,7 @@ use url::form_urlencoded;
 use crate::users::User;
 use crate::Future;
 use crate::Github;
+use crate::Timestamp;
 
 /// Provides access to notifications.
 /// See the [github docs](https://developer.github.com/v3/activity/notifications/)
@@ -64,13 +65,13 @@ impl Notifications {
     /// for more information.
     pub fn mark_as_read<S>(&self, last_read_at: S) -> Future<()>
     where
-        S: Into<Option<String>>,
+        S: Into<Option<Timestamp>>,
     {
         let url = match last_read_at.into() {
             Some(last_read_at) => format!(
                 "/notifications?{}",
                 form_urlencoded::Serializer::new(String::new())
-                    .append_pair("last_read_at", &last_read_at)
+                    .append_pair("last_read_at", &last_read_at.to_string())
                     .finish()
             ),
             None => String::from("/notifications"),
@@ -86,13 +87,13 @@ impl Notifications {
     where
         O: Into<String>,
         R: Into<String>,
-        S: Into<Option<String>>,
+        S: Into<Option<Timestamp>>,
     {
         let path = match last_read_at.into() {
             Some(last_read_at) => format!(
                 "/notifications?{}",
                 form_urlencoded::Serializer::new(String::new())
-                    .append_pair("last_read_at", &last_read_at)
+                    .append_pair("last_read_at", &last_read_at.to_string())
                     .finish()
             ),
             None => String::from("/notifications"),
@@ -190,8 +191,8 @@ impl Notifications {
 pub struct Thread {
     pub id: String,
     pub unread: bool,
-    pub updated_at: String,
-    pub last_read_at: Option<String>,
+    pub updated_at: Timestamp,
+    pub last_read_at: Option<Timestamp>,
     pub reason: String,
     pub subject: Subject,
     pub repository: Repository,
@@ -242,18 +243,18 @@ impl ThreadListOptionsBuilder {
     /// Only show notifications updated after the given time.
     pub fn since<T>(&mut self, since: T) -> &mut Self
     where
-        T: Into<String>,
+        T: Into<Timestamp>,
     {
-        self.0.params.insert("since", since.into());
+        self.0.params.insert("since", since.into().to_string());
         self
     }
 
     /// Only show notifications updated before a given time.
     pub fn before<T>(&mut self, before: T) -> &mut Self
     where
-        T: Into<String>,
+        T: Into<Timestamp>,
     {
-        self.0.params.insert("before", before.into());
+        self.0.params.insert("before", before.into().to_string());
         self
     }
 
@@ -264,6 +265,7 @@ impl ThreadListOptionsBuilder {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct Subject {
     title: String,
@@ -288,7 +290,7 @@ pub struct Subscription {
     pub subscribed: bool,
     pub ignored: bool,
     pub reason: String,
-    pub created_at: String,
+    pub created_at: Timestamp,
     pub url: String,
     pub thread_url: String,
 }
@@ -0,0 +1,543 @@
+//! OAuth user authorization
+//!
+//! Helpers for acquiring user access tokens through the
+//! [web application flow](https://docs.github.com/en/apps/oauth-apps/building-oauth-apps/authorizing-oauth-apps#web-application-flow)
+//! and the [device flow](https://docs.github.com/en/apps/oauth-apps/building-oauth-apps/authorizing-oauth-apps#device-flow),
+//! and for refreshing expiring tokens. These requests are made against the GitHub host, e.g.
+//! `https://github.com`, rather than the API, with the transport and middleware of the client
+//! they are made with but without its credentials.
+//!
+//! ```no_run
+//! use hubcaps::Github;
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let mut github = Github::new("my-cli", None)?;
+//! let oauth = github.oauth("client-id", None);
+//! let code = oauth.request_device_code(&["repo"]).await?;
+//! println!("Enter {} at {}", code.user_code, code.verification_uri);
+//! let token = oauth.poll_device_token(&code).await?;
+//! github.set_credentials(token.credentials());
+//! # Ok(())
+//! # }
+//! ```
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use futures::prelude::*;
+use http::header::{ACCEPT, CONTENT_TYPE, USER_AGENT};
+use http::Method;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::time::delay_for;
+use url::Url;
+
+use crate::errors::{ClientError, OAuthError};
+use crate::{Credentials, Error, Future, Github, Result};
+
+pub(crate) const DEVICE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+/// The amount GitHub asks clients to slow down polling by, unless it says otherwise
+const SLOW_DOWN: Duration = Duration::from_secs(5);
+
+/// Acquires user access tokens for an OAuth app or GitHub app
+#[derive(Clone)]
+pub struct OAuth {
+    github: Github,
+    client_id: String,
+    client_secret: Option<String>,
+}
+
+impl OAuth {
+    pub(crate) fn new<I, S>(github: Github, client_id: I, client_secret: S) -> Self
+    where
+        I: Into<String>,
+        S: Into<Option<String>>,
+    {
+        OAuth {
+            github,
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+        }
+    }
+
+    /// Start the web application flow, returning the url to redirect the user to along with
+    /// the state to verify the user is redirected back with
+    pub fn authorize(&self, options: &AuthorizeOptions) -> Result<Authorization> {
+        let state = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .collect::<String>();
+        let mut url = Url::parse(&self.url("/login/oauth/authorize"))?;
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("client_id", &self.client_id)
+                .append_pair("state", &state);
+            if let Some(redirect_uri) = &options.redirect_uri {
+                query.append_pair("redirect_uri", redirect_uri);
+            }
+            if !options.scopes.is_empty() {
+                query.append_pair("scope", &options.scopes.join(" "));
+            }
+            if let Some(login) = &options.login {
+                query.append_pair("login", login);
+            }
+            if let Some(allow_signup) = options.allow_signup {
+                query.append_pair("allow_signup", &allow_signup.to_string());
+            }
+        }
+        Ok(Authorization {
+            url: url.into(),
+            state,
+            redirect_uri: options.redirect_uri.clone(),
+        })
+    }
+
+    /// Complete the web application flow, exchanging the `code` the user was redirected back
+    /// with for a token once the `state` they were redirected back with is verified
+    pub fn exchange_code(
+        &self,
+        authorization: &Authorization,
+        code: &str,
+        state: &str,
+    ) -> Future<UserAccessToken> {
+        if state != authorization.state {
+            return Box::pin(future::err(Error::OAuth(OAuthError {
+                error: "state_mismatch".into(),
+                error_description: Some(
+                    "The state redirected back with doesn't match the state sent".into(),
+                ),
+                error_uri: None,
+            })));
+        }
+        self.token(&serde_json::json!({
+            "client_id": self.client_id,
+            "client_secret": self.client_secret,
+            "code": code,
+            "redirect_uri": authorization.redirect_uri,
+        }))
+    }
+
+    /// Start the device flow, returning the code the user should enter at the verification uri
+    pub fn request_device_code(&self, scopes: &[&str]) -> Future<DeviceCode> {
+        self.post(
+            "/login/device/code",
+            &serde_json::json!({
+                "client_id": self.client_id,
+                "scope": scopes.join(" "),
+            }),
+        )
+    }
+
+    /// Complete the device flow, polling for a token until the user has entered the code or
+    /// the code expires
+    pub fn poll_device_token(&self, code: &DeviceCode) -> Future<UserAccessToken> {
+        let oauth = self.clone();
+        let code = code.clone();
+        Box::pin(async move {
+            let body = serde_json::json!({
+                "client_id": oauth.client_id,
+                "device_code": code.device_code,
+                "grant_type": DEVICE_GRANT_TYPE,
+            });
+            let mut interval = Duration::from_secs(code.interval);
+            let expires_at = Instant::now() + Duration::from_secs(code.expires_in);
+            loop {
+                // GitHub keeps answering authorization_pending for expired codes
+                if Instant::now() + interval >= expires_at {
+                    return Err(Error::OAuth(OAuthError {
+                        error: "expired_token".into(),
+                        error_description: Some("The device code has expired".into()),
+                        error_uri: None,
+                    }));
+                }
+                delay_for(interval).await;
+                match oauth
+                    .post::<TokenResponse>("/login/oauth/access_token", &body)
+                    .await?
+                {
+                    TokenResponse::Token(token) => return Ok(token.received()),
+                    TokenResponse::Error(error) => match error.error.as_str() {
+                        "authorization_pending" => {}
+                        "slow_down" => {
+                            interval = error
+                                .interval
+                                .map(Duration::from_secs)
+                                .unwrap_or(interval + SLOW_DOWN)
+                        }
+                        _ => return Err(Error::OAuth(error.into())),
+                    },
+                }
+            }
+        })
+    }
+
+    /// Exchange the refresh token of an expiring token for a new token. The refresh token is
+    /// rotated, so the new token's refresh token must be used the next time around
+    pub fn refresh(&self, token: &UserAccessToken) -> Future<UserAccessToken> {
+        match &token.refresh_token {
+            Some(refresh_token) => self.token(&serde_json::json!({
+                "client_id": self.client_id,
+                "client_secret": self.client_secret,
+                "grant_type": "refresh_token",
+                "refresh_token": refresh_token,
+            })),
+            None => Box::pin(future::err(Error::OAuth(OAuthError {
+                error: "missing_refresh_token".into(),
+                error_description: Some("The token can't be refreshed".into()),
+                error_uri: None,
+            }))),
+        }
+    }
+
+    fn token<B>(&self, body: &B) -> Future<UserAccessToken>
+    where
+        B: Serialize,
+    {
+        Box::pin(
+            self.post::<TokenResponse>("/login/oauth/access_token", body)
+                .and_then(|response| {
+                    future::ready(match response {
+                        TokenResponse::Token(token) => Ok(token.received()),
+                        TokenResponse::Error(error) => Err(Error::OAuth(error.into())),
+                    })
+                }),
+        )
+    }
+
+    /// The url of a path on the GitHub host of the client's API, e.g. `https://github.com`
+    fn url(&self, path: &str) -> String {
+        let host = self.github.host.trim_end_matches('/');
+        let host = match host.strip_suffix("/api/v3") {
+            Some(base) => base.to_owned(),
+            None => host.replacen("://api.", "://", 1),
+        };
+        host + path
+    }
+
+    fn post<D>(&self, path: &str, body: &impl Serialize) -> Future<D>
+    where
+        D: DeserializeOwned + 'static + Send,
+    {
+        let request = serde_json::to_vec(body)
+            .map_err(Error::from)
+            .and_then(|body| {
+                let mut request = http::Request::builder()
+                    .method(Method::POST)
+                    .uri(self.url(path))
+                    .header(USER_AGENT, &*self.github.agent)
+                    .header(ACCEPT, "application/json")
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(Some(body))?;
+                for middleware in &self.github.middleware {
+                    middleware.on_request(&mut request)?;
+                }
+                Ok(request)
+            });
+        let request = match request {
+            Ok(request) => request,
+            Err(err) => return Box::pin(future::err(err)),
+        };
+        let method = request.method().clone();
+        let uri = request.uri().clone();
+        let middleware = self.github.middleware.clone();
+        Box::pin(
+            self.github
+                .transport
+                .send(request)
+                .and_then(move |response| async move {
+                    for middleware in middleware.iter().rev() {
+                        middleware.on_response(&method, &uri, &response)?;
+                    }
+                    let status = response.status();
+                    let body = response.into_body();
+                    if status.is_success() {
+                        Ok(serde_json::from_slice(&body)?)
+                    } else if let Ok(error) = serde_json::from_slice::<OAuthError>(&body) {
+                        Err(Error::OAuth(error))
+                    } else {
+                        Err(Error::Fault {
+                            code: status,
+                            error: serde_json::from_slice::<ClientError>(&body)?,
+                        })
+                    }
+                }),
+        )
+    }
+}
+
+// representations
+
+/// Options for starting the web application flow
+#[derive(Debug, Default)]
+pub struct AuthorizeOptions {
+    redirect_uri: Option<String>,
+    scopes: Vec<String>,
+    login: Option<String>,
+    allow_signup: Option<bool>,
+}
+
+impl AuthorizeOptions {
+    pub fn builder() -> AuthorizeOptionsBuilder {
+        AuthorizeOptionsBuilder::default()
+    }
+}
+
+#[derive(Default)]
+pub struct AuthorizeOptionsBuilder(AuthorizeOptions);
+
+impl AuthorizeOptionsBuilder {
+    /// The url in the app the user is sent back to once they authorized it
+    pub fn redirect_uri<R>(&mut self, redirect_uri: R) -> &mut Self
+    where
+        R: Into<String>,
+    {
+        self.0.redirect_uri = Some(redirect_uri.into());
+        self
+    }
+
+    /// The scopes requested, see
+    /// [scopes](https://docs.github.com/en/apps/oauth-apps/building-oauth-apps/scopes-for-oauth-apps)
+    pub fn scopes<S>(&mut self, scopes: Vec<S>) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.0.scopes = scopes.into_iter().map(|s| s.into()).collect();
+        self
+    }
+
+    /// Suggest an account to sign in with
+    pub fn login<L>(&mut self, login: L) -> &mut Self
+    where
+        L: Into<String>,
+    {
+        self.0.login = Some(login.into());
+        self
+    }
+
+    /// Whether users without an account are offered to sign up
+    pub fn allow_signup(&mut self, allow_signup: bool) -> &mut Self {
+        self.0.allow_signup = Some(allow_signup);
+        self
+    }
+
+    pub fn build(&self) -> AuthorizeOptions {
+        AuthorizeOptions {
+            redirect_uri: self.0.redirect_uri.clone(),
+            scopes: self.0.scopes.clone(),
+            login: self.0.login.clone(),
+            allow_signup: self.0.allow_signup,
+        }
+    }
+}
+
+/// A started web application flow
+///
+/// This is serializable so it can be kept, e.g. in a session, until the user is redirected
+/// back.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Authorization {
+    /// The url to send the user to
+    pub url: String,
+    /// The random state the user must be redirected back with
+    pub state: String,
+    pub redirect_uri: Option<String>,
+}
+
+/// A started device flow
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    /// The code the user should enter at the verification uri
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Seconds until the codes expire
+    pub expires_in: u64,
+    /// Minimum seconds between polls for the token
+    pub interval: u64,
+}
+
+/// A user access token
+///
+/// Tokens issued to GitHub apps with expiring user tokens enabled expire, and come with a
+/// refresh token exchanging them for a new token with
+/// [OAuth#refresh](struct.OAuth.html#method.refresh).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UserAccessToken {
+    pub access_token: String,
+    pub token_type: String,
+    #[serde(default)]
+    pub scope: String,
+    /// Seconds the token is valid for after it was received
+    pub expires_in: Option<u64>,
+    pub refresh_token: Option<String>,
+    /// Seconds the refresh token is valid for after it was received
+    pub refresh_token_expires_in: Option<u64>,
+    /// Time the token was received, in seconds since the unix epoch
+    #[serde(default)]
+    pub received_at: u64,
+}
+
+impl UserAccessToken {
+    /// Credentials authenticating requests with this token
+    pub fn credentials(&self) -> Credentials {
+        Credentials::Token(self.access_token.clone())
+    }
+
+    /// Time the token expires at, if it expires
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_in
+            .map(|expires_in| UNIX_EPOCH + Duration::from_secs(self.received_at + expires_in))
+    }
+
+    /// Returns true if the token expires within `margin` from now
+    pub fn expires_within(&self, margin: Duration) -> bool {
+        self.expires_at()
+            .is_some_and(|expires_at| expires_at <= SystemTime::now() + margin)
+    }
+
+    fn received(mut self) -> Self {
+        self.received_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self
+    }
+}
+
+/// GitHub answers token requests with a 200 whether or not they succeed
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TokenResponse {
+    Token(UserAccessToken),
+    Error(PollError),
+}
+
+/// An error polling for a device flow token, which may come with a new polling interval
+#[derive(Deserialize)]
+struct PollError {
+    error: String,
+    error_description: Option<String>,
+    error_uri: Option<String>,
+    interval: Option<u64>,
+}
+
+impl From<PollError> for OAuthError {
+    fn from(error: PollError) -> Self {
+        OAuthError {
+            error: error.error,
+            error_description: error.error_description,
+            error_uri: error.error_uri,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{github, FakeTransport};
+    use http::header::AUTHORIZATION;
+
+    #[tokio::test]
+    async fn oauth_flows() {
+        let transport = FakeTransport::new(vec![
+            (
+                200,
+                r#"{"access_token":"ghu_1","token_type":"bearer","scope":"","expires_in":28800,"refresh_token":"ghr_1","refresh_token_expires_in":15811200}"#,
+            ),
+            (
+                200,
+                r#"{"device_code":"dc","user_code":"ABCD-1234","verification_uri":"https://github.com/login/device","expires_in":900,"interval":0}"#,
+            ),
+            (200, r#"{"error":"authorization_pending"}"#),
+            (200, r#"{"error":"slow_down","interval":0}"#),
+            (
+                200,
+                r#"{"access_token":"gho_2","token_type":"bearer","scope":"repo"}"#,
+            ),
+            (
+                200,
+                r#"{"error":"bad_refresh_token","error_description":"The refresh token passed is incorrect or expired."}"#,
+            ),
+        ]);
+        let oauth = github(&transport).oauth("id", Some("shh".to_owned()));
+
+        let authorization = oauth
+            .authorize(
+                &AuthorizeOptions::builder()
+                    .scopes(vec!["repo", "read:org"])
+                    .build(),
+            )
+            .unwrap();
+        assert_eq!(
+            authorization.url,
+            format!(
+                "https://github.com/login/oauth/authorize?client_id=id&state={}&scope=repo+read%3Aorg",
+                authorization.state
+            )
+        );
+        match oauth.exchange_code(&authorization, "code", "forged").await {
+            Err(Error::OAuth(error)) => assert_eq!(error.error, "state_mismatch"),
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+        let token = oauth
+            .exchange_code(&authorization, "code", &authorization.state)
+            .await
+            .unwrap();
+        assert_eq!(token.credentials(), Credentials::Token("ghu_1".into()));
+        assert!(token.expires_within(Duration::from_secs(8 * 60 * 60)));
+        assert!(!token.expires_within(Duration::from_secs(60)));
+
+        let code = oauth.request_device_code(&["repo"]).await.unwrap();
+        assert_eq!(code.user_code, "ABCD-1234");
+        let token = oauth.poll_device_token(&code).await.unwrap();
+        assert_eq!(token.access_token, "gho_2");
+        assert_eq!(token.expires_at(), None);
+
+        match oauth.refresh(&token).await {
+            Err(Error::OAuth(error)) => assert_eq!(error.error, "missing_refresh_token"),
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+        let token = UserAccessToken {
+            refresh_token: Some("ghr_1".into()),
+            ..token
+        };
+        match oauth.refresh(&token).await {
+            Err(Error::OAuth(error)) => assert_eq!(error.error, "bad_refresh_token"),
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests.len(), 6);
+        assert_eq!(requests[0].1, "https://github.com/login/oauth/access_token");
+        assert_eq!(requests[1].1, "https://github.com/login/device/code");
+        assert!(requests
+            .iter()
+            .all(|(_, _, headers)| !headers.contains_key(AUTHORIZATION)));
+        let bodies = transport.bodies.lock().unwrap();
+        let body = |index: usize| {
+            serde_json::from_slice::<serde_json::Value>(bodies[index].as_ref().unwrap()).unwrap()
+        };
+        assert_eq!(body(0)["client_secret"], "shh");
+        assert_eq!(body(2)["grant_type"], DEVICE_GRANT_TYPE);
+        assert_eq!(body(5)["refresh_token"], "ghr_1");
+    }
+
+    #[tokio::test]
+    async fn oauth_device_codes_expire() {
+        let transport = FakeTransport::new(vec![(200, r#"{"error":"slow_down","interval":5}"#)]);
+        let oauth = github(&transport).oauth("id", None);
+        let code = DeviceCode {
+            device_code: "dc".into(),
+            user_code: "ABCD-1234".into(),
+            verification_uri: "https://github.com/login/device".into(),
+            expires_in: 5,
+            interval: 0,
+        };
+        match oauth.poll_device_token(&code).await {
+            Err(Error::OAuth(error)) => assert_eq!(error.error, "expired_token"),
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+        assert_eq!(transport.requests.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,374 @@
+//! Pages of paginated listings
+//!
+//! Where `list(..)` interfaces yield the items of the first page of a listing and `iter(..)`
+//! interfaces stream the items of all its pages, a [Page](struct.Page.html) exposes a single
+//! page along with its position in the listing, read from the `Link` header GitHub responds
+//! with. Pages can be walked in either direction, or jumped to by number.
+//!
+//! ```no_run
+//! use hubcaps::issues::IssueListOptions;
+//! use hubcaps::Github;
+//!
+//! # async fn run(github: Github) -> hubcaps::Result<()> {
+//! let issues = github.repo("softprops", "hubcaps").issues();
+//! let page = issues
+//!     .page(&IssueListOptions::builder().per_page(50).build())
+//!     .await?;
+//! println!("page 1 of {:?}, ~{:?} issues", page.last_number(), page.total_estimate());
+//! if let Some(last) = page.fetch_last() {
+//!     println!("{} issues on the last page", last.await?.items.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+use std::sync::Arc;
+
+use hyperx::header::{Link, RelationType};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{Future, Github, Result};
+
+/// The number of items per page GitHub defaults to
+const DEFAULT_PER_PAGE: usize = 30;
+
+/// Fetches the page at a url, deserializing it the same way as the page it was linked from
+type Fetch<T> = Arc<dyn Fn(&Github, &str) -> Future<Page<T>> + Send + Sync>;
+
+/// A page of a paginated listing
+pub struct Page<T> {
+    /// The items on this page
+    pub items: Vec<T>,
+    uri: String,
+    next: Option<String>,
+    prev: Option<String>,
+    first: Option<String>,
+    last: Option<String>,
+    github: Github,
+    fetch: Fetch<T>,
+}
+
+impl<T> Page<T> {
+    /// The url of this page
+    pub fn url(&self) -> &str {
+        &self.uri
+    }
+
+    /// The url of the next page, if this isn't the last one
+    pub fn next_url(&self) -> Option<&str> {
+        self.next.as_deref()
+    }
+
+    /// The url of the previous page, if this isn't the first one
+    pub fn prev_url(&self) -> Option<&str> {
+        self.prev.as_deref()
+    }
+
+    /// The url of the first page, if this isn't the first one
+    pub fn first_url(&self) -> Option<&str> {
+        self.first.as_deref()
+    }
+
+    /// The url of the last page, if this isn't the last one
+    pub fn last_url(&self) -> Option<&str> {
+        self.last.as_deref()
+    }
+
+    /// The number of this page, starting at 1
+    pub fn number(&self) -> u32 {
+        // GitHub serves the first page for page=0
+        page_number(&self.uri).unwrap_or(1).max(1)
+    }
+
+    /// The number of the last page of the listing, if GitHub reported it
+    pub fn last_number(&self) -> Option<u32> {
+        match (&self.next, &self.last) {
+            (None, _) => Some(self.number()),
+            (Some(_), Some(last)) => page_number(last),
+            (Some(_), None) => None,
+        }
+    }
+
+    /// The number of items in the listing, if the last page number is known
+    ///
+    /// Unless this is the last page, this is an estimate assuming the last page is full, so
+    /// it may exceed the actual number by up to a page's worth of items.
+    pub fn total_estimate(&self) -> Option<usize> {
+        let per_page = query_param(&self.uri, "per_page").unwrap_or(DEFAULT_PER_PAGE);
+        match self.next {
+            None => Some((self.number() as usize - 1) * per_page + self.items.len()),
+            Some(_) => self.last_number().map(|last| last as usize * per_page),
+        }
+    }
+
+    /// Fetch the next page
+    pub fn fetch_next(&self) -> Option<Future<Page<T>>> {
+        self.next
+            .as_ref()
+            .map(|url| (self.fetch)(&self.github, url))
+    }
+
+    /// Fetch the previous page
+    pub fn fetch_prev(&self) -> Option<Future<Page<T>>> {
+        self.prev
+            .as_ref()
+            .map(|url| (self.fetch)(&self.github, url))
+    }
+
+    /// Fetch the first page
+    pub fn fetch_first(&self) -> Option<Future<Page<T>>> {
+        self.first
+            .as_ref()
+            .map(|url| (self.fetch)(&self.github, url))
+    }
+
+    /// Fetch the last page
+    pub fn fetch_last(&self) -> Option<Future<Page<T>>> {
+        self.last
+            .as_ref()
+            .map(|url| (self.fetch)(&self.github, url))
+    }
+
+    /// Fetch the page with the given number, starting at 1. Pages past the last one are empty
+    pub fn fetch_page(&self, number: u32) -> Future<Page<T>> {
+        match page_url(&self.uri, number) {
+            Ok(url) => (self.fetch)(&self.github, &url),
+            Err(err) => Box::pin(async move { Err(err) }),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Page<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Page")
+            .field("items", &self.items)
+            .field("uri", &self.uri)
+            .field("next", &self.next)
+            .field("prev", &self.prev)
+            .field("first", &self.first)
+            .field("last", &self.last)
+            .finish()
+    }
+}
+
+/// A position in a paginated listing, from which a stream over it can be resumed
+///
+/// Resumable streams, like [Issues#iter_resumable](../issues/struct.Issues.html#method.iter_resumable),
+/// yield each item along with the cursor following it. Saving the cursor of the last item
+/// processed and passing it to [Github#resume_stream](../struct.Github.html#method.resume_stream)
+/// later on continues the stream with the next item.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    /// The url of a page of the listing
+    pub url: String,
+    /// The number of items on the page preceding the position
+    pub offset: usize,
+}
+
+impl Cursor {
+    /// A cursor at the start of the page at `url`
+    pub fn new<U>(url: U) -> Self
+    where
+        U: Into<String>,
+    {
+        Cursor {
+            url: url.into(),
+            offset: 0,
+        }
+    }
+
+    /// Pair the items of the page at `url` from `offset` onwards with the cursors following them
+    pub(crate) fn items<I>(url: &str, offset: usize, items: Vec<I>) -> Vec<(Cursor, I)> {
+        items
+            .into_iter()
+            .enumerate()
+            .skip(offset)
+            .map(|(index, item)| {
+                let cursor = Cursor {
+                    url: url.to_owned(),
+                    offset: index + 1,
+                };
+                (cursor, item)
+            })
+            .collect()
+    }
+}
+
+/// Fetch the page of a listing at `uri`, extracting its items with `into_items`
+pub(crate) fn get<D, I>(github: &Github, uri: &str, into_items: fn(D) -> Vec<I>) -> Future<Page<I>>
+where
+    D: DeserializeOwned + 'static + Send,
+    I: 'static + Send,
+{
+    let github = github.clone();
+    let uri = github.absolute_url(uri);
+    Box::pin(async move {
+        let (link, payload) = github.get_pages::<D>(&uri).await?;
+        let rel = |rel: RelationType| link.as_ref().and_then(|link| link_url(link, rel));
+        Ok(Page {
+            items: into_items(payload),
+            next: rel(RelationType::Next),
+            prev: rel(RelationType::Prev),
+            first: rel(RelationType::First),
+            last: rel(RelationType::Last),
+            uri,
+            github,
+            fetch: Arc::new(move |github: &Github, uri: &str| get(github, uri, into_items)),
+        })
+    })
+}
+
+/// The urls of all pages following the one `link` was read from, if it links to the last page
+/// by number
+pub(crate) fn following(link: &Link) -> Option<Vec<String>> {
+    let next = link_url(link, RelationType::Next)?;
+    let last = page_number(&link_url(link, RelationType::Last)?)?;
+    (page_number(&next)?..=last)
+        .map(|number| page_url(&next, number))
+        .collect::<Result<_>>()
+        .ok()
+}
+
+fn link_url(link: &Link, rel: RelationType) -> Option<String> {
+    link.values().iter().find_map(|value| {
+        value
+            .rel()
+            .filter(|rels| rels.contains(&rel))
+            .map(|_| value.link().to_owned())
+    })
+}
+
+fn query_param<T>(url: &str, name: &str) -> Option<T>
+where
+    T: std::str::FromStr,
+{
+    Url::parse(url)
+        .ok()?
+        .query_pairs()
+        .find(|(key, _)| key == name)
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+pub(crate) fn page_number(url: &str) -> Option<u32> {
+    query_param(url, "page")
+}
+
+/// Replace the page number in a listing url
+fn page_url(url: &str, number: u32) -> Result<String> {
+    let mut url = Url::parse(url)?;
+    let query = url
+        .query_pairs()
+        .filter(|(key, _)| key != "page")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(query)
+        .append_pair("page", &number.to_string());
+    Ok(url.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{github, FakeTransport};
+    use crate::{AuthenticationConstraint, MediaType};
+    use futures::prelude::*;
+    use http::header::ACCEPT;
+    use http::Method;
+
+    #[test]
+    fn replaces_page_numbers() {
+        let url = "https://api.github.com/repos/o/r/issues?state=all&page=2&per_page=50";
+        assert_eq!(page_number(url), Some(2));
+        assert_eq!(
+            page_url(url, 5).unwrap(),
+            "https://api.github.com/repos/o/r/issues?state=all&per_page=50&page=5"
+        );
+        assert_eq!(
+            page_url("https://api.github.com/user/repos", 3).unwrap(),
+            "https://api.github.com/user/repos?page=3"
+        );
+
+        let link = "<https://api.github.com/user/repos?page=2>; rel=\"next\", \
+                    <https://api.github.com/user/repos?page=4>; rel=\"last\""
+            .parse()
+            .unwrap();
+        assert_eq!(
+            following(&link),
+            Some(vec![
+                "https://api.github.com/user/repos?page=2".to_owned(),
+                "https://api.github.com/user/repos?page=3".to_owned(),
+                "https://api.github.com/user/repos?page=4".to_owned(),
+            ])
+        );
+        let cursors = "<https://api.github.com/user/repos?after=abc>; rel=\"next\""
+            .parse()
+            .unwrap();
+        assert_eq!(following(&cursors), None);
+    }
+
+    #[tokio::test]
+    async fn raw_requests_and_streams() {
+        let transport = FakeTransport::new(vec![
+            (201, r#"{"id":1}"#),
+            (204, ""),
+            (200, r#"{"total_count":1,"installations":[{"id":2}]}"#),
+        ]);
+        let github = github(&transport);
+        let created: serde_json::Value = github
+            .raw_request(
+                Method::POST,
+                "/orgs/o/teams/t/discussions",
+                Some(&serde_json::json!({ "title": "hi" })),
+                MediaType::Preview("echo"),
+                AuthenticationConstraint::Unconstrained,
+            )
+            .await
+            .unwrap();
+        assert_eq!(created["id"], 1);
+        github
+            .raw_request::<(), ()>(
+                Method::DELETE,
+                "https://api.github.com/orgs/o/teams/t",
+                None,
+                MediaType::Json,
+                AuthenticationConstraint::Unconstrained,
+            )
+            .await
+            .unwrap();
+        let ids = github
+            .raw_stream_items("/user/installations", |page: serde_json::Value| {
+                page["installations"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .map_ok(|installation| installation["id"].clone())
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(ids, vec![2]);
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(
+            requests[0].1,
+            "https://api.github.com/orgs/o/teams/t/discussions"
+        );
+        assert_eq!(
+            requests[0].2[ACCEPT],
+            "application/vnd.github.echo-preview+json"
+        );
+        assert_eq!(requests[1].1, "https://api.github.com/orgs/o/teams/t");
+        assert_eq!(
+            transport.bodies.lock().unwrap()[1],
+            None,
+            "requests without a body"
+        );
+    }
+}
@@ -2,7 +2,7 @@
 use serde::Deserialize;
 
 use crate::users::User;
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, Stream, Timestamp};
 
 /// A structure for interfacing with a pull commits
 pub struct PullCommits {
@@ -83,5 +83,5 @@ pub struct CommitRef {
 pub struct UserStamp {
     pub name: String,
     pub email: String,
-    pub date: String,
+    pub date: Timestamp,
 }
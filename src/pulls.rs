@@ -8,16 +8,18 @@ use url::form_urlencoded;
 use crate::comments::Comments;
 use crate::issues::{IssueAssignees, IssueLabels, Sort as IssueSort, State};
 use crate::labels::Label;
+use crate::page::Page;
 use crate::pull_commits::PullCommits;
 use crate::review_comments::ReviewComments;
 use crate::review_requests::ReviewRequests;
 use crate::users::User;
-use crate::{Future, Github, SortDirection, Stream};
+use crate::{Future, Github, SortDirection, Stream, Timestamp};
 
 /// Sort directions for pull requests
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum Sort {
     /// Sort by time created
+    #[default]
     Created,
     /// Sort by last updated
     Updated,
@@ -39,12 +41,6 @@ impl fmt::Display for Sort {
     }
 }
 
-impl Default for Sort {
-    fn default() -> Sort {
-        Sort::Created
-    }
-}
-
 /// A structure for accessing interfacing with a specific pull request
 pub struct PullRequest {
     github: Github,
@@ -217,6 +213,15 @@ impl PullRequests {
         }
         self.github.get_stream(&uri.join("?"))
     }
+
+    /// Return a single page of pull requests, which can be used to navigate to the pages around it
+    pub fn page(&self, options: &PullListOptions) -> Future<Page<Pull>> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_page(&uri.join("?"))
+    }
 }
 
 // representations (todo: replace with derive_builder)
@@ -239,10 +244,10 @@ pub struct Pull {
     pub state: String,
     pub title: String,
     pub body: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
-    pub closed_at: Option<String>,
-    pub merged_at: Option<String>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    pub closed_at: Option<Timestamp>,
+    pub merged_at: Option<Timestamp>,
     pub head: Commit,
     pub base: Commit,
     // links
@@ -1,7 +1,22 @@
 //! Rate Limit interface
-use crate::{Future, Github};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use http::header::HeaderMap;
+use http::Method;
 use serde::Deserialize;
 
+use crate::{
+    Credentials, Error, Future, Github, Result, X_RATELIMIT_LIMIT, X_RATELIMIT_REMAINING,
+    X_RATELIMIT_RESET, X_RATELIMIT_RESOURCE,
+};
+
+/// How long a member of a `CredentialPool` is skipped for after being rejected for exceeding
+/// the rate limit, at least, and when GitHub doesn't report when its budget resets
+const UNKNOWN_RESET: Duration = Duration::from_secs(60);
+
 pub struct RateLimit {
     github: Github,
 }
@@ -38,3 +53,669 @@ pub struct RateLimitResourceStatus {
     pub remaining: u32,
     pub reset: u32, // ideally something like std::time::Duration
 }
+
+/// A rate limited GitHub API resource. Each resource has a separate budget
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Resource {
+    /// The REST api
+    Core,
+    /// The search api
+    Search,
+    /// The GraphQL api
+    GraphQL,
+}
+
+impl Resource {
+    /// Infer the resource a request to the given uri is accounted against
+    pub fn from_uri(uri: &str) -> Resource {
+        let path = uri.split('?').next().unwrap_or_default();
+        // only the path of the api is telling, not the scheme and host
+        let path = match path.find("://") {
+            Some(scheme) => {
+                let authority = &path[scheme + 3..];
+                authority.find('/').map_or("", |slash| &authority[slash..])
+            }
+            None => path,
+        };
+        // GitHub Enterprise serves the api under /api/v3, and GraphQL at /api/graphql
+        let path = path.strip_prefix("/api/v3").unwrap_or(path);
+        if path == "/graphql" || path == "/api/graphql" {
+            Resource::GraphQL
+        } else if path.starts_with("/search/") {
+            Resource::Search
+        } else {
+            Resource::Core
+        }
+    }
+
+    /// Determine the resource a response to a request to `uri` was accounted against
+    fn from_response(uri: &str, headers: &HeaderMap) -> Option<Resource> {
+        headers
+            .get(X_RATELIMIT_RESOURCE)
+            .and_then(|value| value.to_str().ok())
+            .map(Resource::from_header)
+            .unwrap_or_else(|| Some(Resource::from_uri(uri)))
+    }
+
+    fn from_header(value: &str) -> Option<Resource> {
+        match value {
+            "core" => Some(Resource::Core),
+            "search" => Some(Resource::Search),
+            "graphql" => Some(Resource::GraphQL),
+            _ => None,
+        }
+    }
+}
+
+/// A snapshot of the budget of a resource as last reported by GitHub
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Budget {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time at which the budget resets, in seconds since the unix epoch
+    pub reset: u32,
+}
+
+impl Budget {
+    /// Parse a budget from `x-ratelimit-*` response headers
+    pub fn from_headers(headers: &HeaderMap) -> Option<Budget> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u32>().ok())
+        };
+        Some(Budget {
+            limit: header(X_RATELIMIT_LIMIT)?,
+            remaining: header(X_RATELIMIT_REMAINING)?,
+            reset: header(X_RATELIMIT_RESET)?,
+        })
+    }
+
+    /// Duration until this budget resets
+    pub fn reset_in(&self) -> Duration {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        Duration::from_secs(u64::from(self.reset).saturating_sub(now))
+    }
+}
+
+/// Paces requests according to the rate limit budgets reported by GitHub
+///
+/// A governor is opt-in, see [Github#set_governor](../struct.Github.html#method.set_governor).
+/// Clones of a governor, and of clients using it, share the same budgets so a single governor
+/// can coordinate all requests made with a set of credentials.
+///
+/// Once a resource's budget is exhausted, requests wait for the budget to reset instead of
+/// failing with `Error::RateLimit`. If a slow down threshold is configured, requests made
+/// once the remaining budget drops below the threshold are spread out evenly over the
+/// time left until the reset.
+#[derive(Clone, Debug, Default)]
+pub struct Governor {
+    threshold: Option<u32>,
+    budgets: Arc<Mutex<HashMap<Resource, Budget>>>,
+    /// When the last request slowed down for each resource is due to be sent
+    slots: Arc<Mutex<HashMap<Resource, Instant>>>,
+}
+
+impl Governor {
+    /// Create a governor which waits for a reset once a budget is exhausted
+    pub fn new() -> Self {
+        Governor::default()
+    }
+
+    /// Create a governor which also slows requests down once the remaining budget drops to
+    /// `threshold`
+    pub fn slow_down(threshold: u32) -> Self {
+        Governor {
+            threshold: Some(threshold),
+            ..Governor::default()
+        }
+    }
+
+    /// Return the last known budget for a resource
+    pub fn budget(&self, resource: Resource) -> Option<Budget> {
+        self.budgets.lock().unwrap().get(&resource).cloned()
+    }
+
+    /// Return the last known budgets for all resources
+    pub fn snapshot(&self) -> HashMap<Resource, Budget> {
+        self.budgets.lock().unwrap().clone()
+    }
+
+    /// Reserve a request against a resource's budget, returning how long to wait before
+    /// sending it
+    pub(crate) fn acquire(&self, resource: Resource) -> Option<Duration> {
+        let mut budgets = self.budgets.lock().unwrap();
+        let budget = budgets.get_mut(&resource)?;
+        let reset_in = budget.reset_in();
+        if reset_in == Duration::from_secs(0) {
+            // the budget has been reset since we last heard from GitHub
+            budgets.remove(&resource);
+            self.slots.lock().unwrap().remove(&resource);
+            return None;
+        }
+        if budget.remaining == 0 {
+            // allow for clock drift between us and GitHub
+            return Some(reset_in + Duration::from_secs(1));
+        }
+        budget.remaining -= 1;
+        let interval = match self.threshold {
+            Some(threshold) if budget.remaining < threshold => reset_in / (budget.remaining + 1),
+            _ => return None,
+        };
+        // reserve the slot after the last one handed out, so concurrent requests are spread
+        // out rather than all waiting the same interval
+        let mut slots = self.slots.lock().unwrap();
+        let now = Instant::now();
+        let slot = match slots.get(&resource) {
+            Some(&last) if last > now => last,
+            _ => now,
+        } + interval;
+        slots.insert(resource, slot);
+        Some(slot - now)
+    }
+
+    /// Record the budget reported in response headers
+    pub(crate) fn update(&self, uri: &str, headers: &HeaderMap) {
+        let resource = Resource::from_response(uri, headers);
+        if let (Some(resource), Some(budget)) = (resource, Budget::from_headers(headers)) {
+            self.budgets.lock().unwrap().insert(resource, budget);
+        }
+    }
+}
+
+/// Spreads requests across several credentials, each with its own rate limit budget
+///
+/// Set a pool as the credentials of a client with `Credentials::Pool`. Each request is made
+/// with the member with the most remaining budget for the resource it's accounted against,
+/// as last reported in response headers, trying members whose budget isn't known yet first.
+/// Members whose budget is exhausted are skipped until it resets, and requests rejected for
+/// exceeding the rate limit are retried with another member. Once every member's budget is
+/// exhausted, requests fail with `Error::RateLimit` carrying the time until the earliest reset.
+///
+/// Clones of a pool, and of clients using it, share the same budgets. Pools are meant to replace
+/// a [Governor](struct.Governor.html), which accounts all requests of a client against a single
+/// budget.
+///
+/// ```no_run
+/// use hubcaps::rate_limit::CredentialPool;
+/// use hubcaps::{Credentials, Github};
+///
+/// # fn main() -> hubcaps::Result<()> {
+/// let pool = CredentialPool::new(vec![
+///     Credentials::Token("first-token".into()),
+///     Credentials::Token("second-token".into()),
+/// ]);
+/// let github = Github::new("user-agent-name", Credentials::Pool(pool))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CredentialPool {
+    members: Arc<Mutex<Vec<Member>>>,
+}
+
+struct Member {
+    credentials: Credentials,
+    budgets: HashMap<Resource, Budget>,
+}
+
+impl CredentialPool {
+    /// Create a pool of the given credentials. Members which are pools themselves are merged
+    /// into this one
+    pub fn new<I>(credentials: I) -> Self
+    where
+        I: IntoIterator<Item = Credentials>,
+    {
+        let mut members = Vec::new();
+        for credentials in credentials {
+            match credentials {
+                Credentials::Pool(pool) => members.extend(pool.credentials()),
+                credentials => members.push(credentials),
+            }
+        }
+        CredentialPool {
+            members: Arc::new(Mutex::new(
+                members
+                    .into_iter()
+                    .map(|credentials| Member {
+                        credentials,
+                        budgets: HashMap::new(),
+                    })
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Return the credentials of the members of this pool
+    pub fn credentials(&self) -> Vec<Credentials> {
+        let members = self.members.lock().unwrap();
+        members
+            .iter()
+            .map(|member| member.credentials.clone())
+            .collect()
+    }
+
+    /// Return the last known budget of each member for a resource, in the order the members
+    /// were added in
+    pub fn budgets(&self, resource: Resource) -> Vec<Option<Budget>> {
+        let members = self.members.lock().unwrap();
+        members
+            .iter()
+            .map(|member| member.budgets.get(&resource).cloned())
+            .collect()
+    }
+
+    /// The number of members in the pool
+    pub(crate) fn len(&self) -> usize {
+        self.members.lock().unwrap().len()
+    }
+
+    /// Reserve a request against the budget of the member with the most remaining budget for
+    /// a resource, returning its index and credentials, or `None` if the pool is empty
+    pub(crate) fn acquire(&self, resource: Resource) -> Result<Option<(usize, Credentials)>> {
+        let mut members = self.members.lock().unwrap();
+        let mut reset = None;
+        let mut best: Option<(usize, Option<u32>)> = None;
+        for (index, member) in members.iter_mut().enumerate() {
+            let remaining = match member.budgets.get(&resource) {
+                Some(budget) if budget.reset_in() == Duration::from_secs(0) => {
+                    // the budget has been reset since we last heard from GitHub
+                    member.budgets.remove(&resource);
+                    None
+                }
+                Some(budget) if budget.remaining == 0 => {
+                    let reset_in = budget.reset_in();
+                    reset = Some(reset.map_or(reset_in, |reset: Duration| reset.min(reset_in)));
+                    continue;
+                }
+                Some(budget) => Some(budget.remaining),
+                None => None,
+            };
+            // members without a known budget are tried first to learn it
+            let better = match best {
+                None => true,
+                Some((_, best)) => match (best, remaining) {
+                    (Some(best), Some(remaining)) => remaining > best,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                },
+            };
+            if better {
+                best = Some((index, remaining));
+            }
+        }
+        match (best, reset) {
+            (Some((index, _)), _) => {
+                let member = &mut members[index];
+                if let Some(budget) = member.budgets.get_mut(&resource) {
+                    budget.remaining -= 1;
+                }
+                Ok(Some((index, member.credentials.clone())))
+            }
+            (None, Some(reset)) => Err(Error::RateLimit {
+                // allow for clock drift between us and GitHub
+                reset: reset + Duration::from_secs(1),
+            }),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Record the budget reported in the headers of a response to a request made with a member
+    pub(crate) fn update(&self, index: usize, uri: &str, headers: &HeaderMap) {
+        let resource = Resource::from_response(uri, headers);
+        if let (Some(resource), Some(budget)) = (resource, Budget::from_headers(headers)) {
+            if let Some(member) = self.members.lock().unwrap().get_mut(index) {
+                member.budgets.insert(resource, budget);
+            }
+        }
+    }
+
+    /// Mark the budget of a member exhausted after a request made with it was rejected for
+    /// exceeding the rate limit
+    pub(crate) fn exhaust(&self, index: usize, uri: &str, headers: &HeaderMap) {
+        let resource =
+            Resource::from_response(uri, headers).unwrap_or_else(|| Resource::from_uri(uri));
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        // a reset already past, or missing, would have the member tried again right away
+        let reset = (now + UNKNOWN_RESET.as_secs()) as u32;
+        let budget = match Budget::from_headers(headers) {
+            Some(budget) => Budget {
+                remaining: 0,
+                reset: budget.reset.max(reset),
+                ..budget
+            },
+            None => Budget {
+                limit: 0,
+                remaining: 0,
+                reset,
+            },
+        };
+        if let Some(member) = self.members.lock().unwrap().get_mut(index) {
+            member.budgets.insert(resource, budget);
+        }
+    }
+}
+
+impl fmt::Debug for CredentialPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let members = self.members.lock().unwrap();
+        f.debug_list()
+            .entries(members.iter().map(|member| &member.credentials))
+            .finish()
+    }
+}
+
+impl PartialEq for CredentialPool {
+    fn eq(&self, other: &CredentialPool) -> bool {
+        Arc::ptr_eq(&self.members, &other.members) || self.credentials() == other.credentials()
+    }
+}
+
+/// Spaces out content creating requests to avoid triggering GitHub's secondary rate limits
+///
+/// GitHub [recommends](https://docs.github.com/en/rest/guides/best-practices-for-integrators#dealing-with-secondary-rate-limits)
+/// waiting at least one second between `POST`, `PATCH`, `PUT` and `DELETE` requests. A pacer is
+/// opt-in, see [Github#set_pacer](../struct.Github.html#method.set_pacer). Clones of a pacer,
+/// and of clients using it, share the same schedule.
+#[derive(Clone, Debug)]
+pub struct Pacer {
+    interval: Duration,
+    next: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Pacer {
+    /// Create a pacer which waits at least `interval` between content creating requests
+    pub fn new(interval: Duration) -> Self {
+        Pacer {
+            interval,
+            next: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns true if requests with the given method are paced
+    pub fn paces(&self, method: &Method) -> bool {
+        [Method::POST, Method::PATCH, Method::PUT, Method::DELETE].contains(method)
+    }
+
+    /// Reserve the next slot, returning how long to wait until it starts
+    pub(crate) fn acquire(&self) -> Option<Duration> {
+        let mut next = self.next.lock().unwrap();
+        let now = Instant::now();
+        let slot = match *next {
+            Some(next) if next > now => next,
+            _ => now,
+        };
+        *next = Some(slot + self.interval);
+        if slot > now {
+            Some(slot - now)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Pacer {
+    /// A pacer following GitHub's recommendation of one second between requests
+    fn default() -> Self {
+        Pacer::new(Duration::from_secs(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::RetryPolicy;
+    use crate::testing::{github, FakeTransport};
+    use http::header::HeaderValue;
+    use http::header::AUTHORIZATION;
+
+    fn headers(remaining: u32, reset_in: u64) -> HeaderMap {
+        let reset = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + reset_in;
+        let mut headers = HeaderMap::new();
+        headers.insert(X_RATELIMIT_LIMIT, HeaderValue::from_static("5000"));
+        headers.insert(X_RATELIMIT_REMAINING, remaining.into());
+        headers.insert(X_RATELIMIT_RESET, reset.into());
+        headers
+    }
+
+    #[test]
+    fn resource_from_uri() {
+        assert_eq!(
+            Resource::from_uri("https://api.github.com/repos/o/r/issues"),
+            Resource::Core
+        );
+        assert_eq!(
+            Resource::from_uri("https://api.github.com/search/issues?q=foo"),
+            Resource::Search
+        );
+        assert_eq!(
+            Resource::from_uri("https://api.github.com/graphql"),
+            Resource::GraphQL
+        );
+        assert_eq!(
+            Resource::from_uri("https://ghe.example.com/api/v3/search/code?q=foo"),
+            Resource::Search
+        );
+        assert_eq!(
+            Resource::from_uri("https://ghe.example.com/api/graphql"),
+            Resource::GraphQL
+        );
+        for uri in &[
+            "https://api.github.com/repos/o/search/issues",
+            "https://api.github.com/users/search/repos",
+            "https://ghe.example.com/api/v3/repos/o/search/issues",
+            "https://api.github.com/repos/o/graphql",
+        ] {
+            assert_eq!(Resource::from_uri(uri), Resource::Core, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn governor_waits_for_exhausted_budget() {
+        let governor = Governor::new();
+        assert_eq!(governor.acquire(Resource::Core), None);
+        governor.update("https://api.github.com/user", &headers(1, 60));
+        assert_eq!(governor.acquire(Resource::Core), None);
+        assert!(governor.acquire(Resource::Core).unwrap() > Duration::from_secs(59));
+        assert_eq!(governor.acquire(Resource::Search), None);
+    }
+
+    #[test]
+    fn governor_slows_down_below_threshold() {
+        let governor = Governor::slow_down(10);
+        governor.update("https://api.github.com/user", &headers(100, 60));
+        assert_eq!(governor.acquire(Resource::Core), None);
+        governor.update("https://api.github.com/user", &headers(5, 60));
+        let delay = governor.acquire(Resource::Core).unwrap();
+        assert!(delay > Duration::from_secs(9) && delay <= Duration::from_secs(12));
+        // a request made meanwhile waits for the slot after
+        let delay = governor.acquire(Resource::Core).unwrap();
+        assert!(delay > Duration::from_secs(21) && delay <= Duration::from_secs(27));
+    }
+
+    #[test]
+    fn pacer_spaces_out_requests() {
+        let pacer = Pacer::new(Duration::from_secs(10));
+        assert!(pacer.paces(&Method::POST));
+        assert!(!pacer.paces(&Method::GET));
+        assert_eq!(pacer.acquire(), None);
+        let first = pacer.acquire().unwrap();
+        let second = pacer.acquire().unwrap();
+        assert!(first > Duration::from_secs(9) && first <= Duration::from_secs(10));
+        assert!(second > Duration::from_secs(19) && second <= Duration::from_secs(20));
+    }
+
+    #[test]
+    fn pool_prefers_members_with_most_budget() {
+        let pool = CredentialPool::new(vec![
+            Credentials::Token("first".into()),
+            Credentials::Pool(CredentialPool::new(vec![Credentials::Token(
+                "second".into(),
+            )])),
+        ]);
+        let uri = "https://api.github.com/user";
+        assert_eq!(pool.credentials().len(), 2);
+        assert_eq!(pool.acquire(Resource::Core).unwrap().unwrap().0, 0);
+        pool.update(0, uri, &headers(10, 60));
+        assert_eq!(pool.acquire(Resource::Core).unwrap().unwrap().0, 1);
+        pool.update(1, uri, &headers(5, 60));
+        assert_eq!(pool.acquire(Resource::Core).unwrap().unwrap().0, 0);
+        assert_eq!(pool.budgets(Resource::Core)[0].unwrap().remaining, 9);
+
+        pool.exhaust(0, uri, &HeaderMap::new());
+        assert_eq!(pool.acquire(Resource::Core).unwrap().unwrap().0, 1);
+        pool.update(1, uri, &headers(0, 30));
+        match pool.acquire(Resource::Core) {
+            Err(Error::RateLimit { reset }) => {
+                assert!(reset > Duration::from_secs(29) && reset <= Duration::from_secs(31))
+            }
+            other => panic!("expected a rate limit error, got {:?}", other),
+        }
+        assert!(pool.acquire(Resource::Search).unwrap().is_some());
+        assert!(CredentialPool::new(vec![])
+            .acquire(Resource::Core)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn governor_updates_resource_from_header() {
+        let governor = Governor::new();
+        let mut headers = headers(10, 60);
+        headers.insert(X_RATELIMIT_RESOURCE, HeaderValue::from_static("search"));
+        governor.update("https://api.github.com/user", &headers);
+        assert_eq!(governor.budget(Resource::Core), None);
+        assert_eq!(governor.budget(Resource::Search).unwrap().remaining, 10);
+    }
+
+    #[tokio::test]
+    async fn pools_fail_over_on_rate_limits() {
+        let budget = |remaining: u32| {
+            let reset = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                + 3600;
+            let mut headers = HeaderMap::new();
+            headers.insert(X_RATELIMIT_LIMIT, HeaderValue::from_static("5000"));
+            headers.insert(X_RATELIMIT_REMAINING, remaining.into());
+            headers.insert(X_RATELIMIT_RESET, reset.into());
+            headers
+        };
+        let limited = r#"{"message":"API rate limit exceeded"}"#;
+        let transport = FakeTransport::new(vec![
+            (200, "{}"),
+            (200, "{}"),
+            (403, limited),
+            (200, "{}"),
+            (403, limited),
+        ])
+        .with_headers(vec![
+            budget(10),
+            budget(4000),
+            budget(0),
+            budget(8),
+            budget(0),
+        ]);
+        let pool = CredentialPool::new(vec![
+            Credentials::Token("first".into()),
+            Credentials::Token("second".into()),
+        ]);
+        let mut github = github(&transport);
+        github.set_credentials(Credentials::Pool(pool.clone()));
+
+        for _ in 0..3 {
+            github.get::<serde_json::Value>("/user").await.unwrap();
+        }
+        match github.get::<serde_json::Value>("/user").await {
+            Err(Error::RateLimit { reset }) => assert!(reset > Duration::from_secs(3500)),
+            other => panic!("expected a rate limit error, got {:?}", other),
+        }
+        let budgets = pool.budgets(Resource::Core);
+        assert_eq!(budgets[0].map(|budget| budget.remaining), Some(0));
+        assert_eq!(budgets[1].map(|budget| budget.remaining), Some(0));
+
+        let requests = transport.requests.lock().unwrap();
+        let tokens = requests
+            .iter()
+            .map(|(_, _, headers)| headers[AUTHORIZATION].to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                "token first",
+                "token second",
+                "token second",
+                "token first",
+                "token first"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn pools_skip_members_rate_limited_with_past_resets() {
+        let mut past = HeaderMap::new();
+        past.insert(X_RATELIMIT_LIMIT, HeaderValue::from_static("5000"));
+        past.insert(X_RATELIMIT_REMAINING, HeaderValue::from_static("0"));
+        past.insert(X_RATELIMIT_RESET, HeaderValue::from_static("1"));
+        let limited = r#"{"message":"API rate limit exceeded"}"#;
+        let transport = FakeTransport::new(vec![(403, limited); 4]).with_headers(vec![
+            past.clone(),
+            past.clone(),
+            past.clone(),
+            past,
+        ]);
+        let pool = CredentialPool::new(vec![
+            Credentials::Token("first".into()),
+            Credentials::Token("second".into()),
+        ]);
+        let mut github = github(&transport);
+        github.set_credentials(Credentials::Pool(pool));
+
+        match github.get::<serde_json::Value>("/user").await {
+            Err(Error::RateLimit { reset }) => assert!(reset >= Duration::from_secs(59)),
+            other => panic!("expected a rate limit error, got {:?}", other),
+        }
+        assert_eq!(transport.requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn pool_failovers_leave_retries_alone() {
+        let limited = r#"{"message":"API rate limit exceeded"}"#;
+        let mut exhausted = HeaderMap::new();
+        exhausted.insert(X_RATELIMIT_REMAINING, HeaderValue::from_static("0"));
+        let transport = FakeTransport::new(vec![
+            (403, limited),
+            (502, "bad gateway"),
+            (502, "bad gateway"),
+            (200, "{}"),
+        ])
+        .with_headers(vec![exhausted]);
+        let pool = CredentialPool::new(vec![
+            Credentials::Token("first".into()),
+            Credentials::Token("second".into()),
+        ]);
+        let mut github = github(&transport);
+        github.set_credentials(Credentials::Pool(pool));
+        github.set_retry_policy(
+            RetryPolicy::builder()
+                .initial_backoff(Duration::from_millis(1))
+                .jitter(false)
+                .build(),
+        );
+
+        github.get::<serde_json::Value>("/user").await.unwrap();
+        assert_eq!(transport.requests.lock().unwrap().len(), 4);
+    }
+}
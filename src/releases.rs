@@ -2,7 +2,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Timestamp};
 
 /// Provides access to assets for a release.
 /// See the [github docs](https://developer.github.com/v3/repos/releases/)
@@ -218,8 +218,8 @@ pub struct Asset {
     pub content_type: String,
     pub size: u64,
     pub download_count: u64,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
     pub uploader: User,
 }
 
@@ -238,8 +238,8 @@ pub struct Release {
     pub body: String,
     pub draft: bool,
     pub prerelease: bool,
-    pub created_at: String,
-    pub published_at: String,
+    pub created_at: Timestamp,
+    pub published_at: Timestamp,
     pub author: User,
     pub assets: Vec<Asset>,
 }
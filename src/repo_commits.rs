@@ -3,7 +3,7 @@
 use serde::Deserialize;
 
 use crate::users::User;
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, Stream, Timestamp};
 
 /// A structure for interfacing with a repository commits
 pub struct RepoCommits {
@@ -91,5 +91,5 @@ pub struct CommitRef {
 pub struct UserStamp {
     pub name: String,
     pub email: String,
-    pub date: String,
+    pub date: Timestamp,
 }
@@ -15,6 +15,7 @@ use crate::hooks::Hooks;
 use crate::issues::{IssueRef, Issues};
 use crate::keys::Keys;
 use crate::labels::Labels;
+use crate::page::Cursor;
 use crate::pulls::PullRequests;
 use crate::releases::Releases;
 use crate::repo_commits::RepoCommits;
@@ -23,7 +24,7 @@ use crate::teams::RepoTeams;
 use crate::traffic::Traffic;
 use crate::users::Contributors;
 use crate::users::User;
-use crate::{Future, Github, SortDirection, Stream};
+use crate::{Future, Github, SortDirection, Stream, Timestamp};
 
 /// describes repository visibilities
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -172,6 +173,17 @@ impl Repositories {
         }
         self.github.get_stream(&uri.join("?"))
     }
+
+    /// provides a stream over all pages of the authenticated users repositories, along with the cursor following each of
+    /// them, which can be used to resume the stream later with
+    /// [Github#resume_stream](../struct.Github.html#method.resume_stream)
+    pub fn iter_resumable(&self, options: &RepoListOptions) -> Stream<(Cursor, Repo)> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.resume_stream(&Cursor::new(uri.join("?")))
+    }
 }
 
 pub struct Forks {
@@ -581,9 +593,9 @@ pub struct Repo {
     pub has_pages: bool,
     pub has_downloads: bool,
     pub archived: bool,
-    pub pushed_at: String,
-    pub created_at: String,
-    pub updated_at: String, // permissions: Permissions
+    pub pushed_at: Timestamp,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp, // permissions: Permissions
 }
 
 impl Repo {
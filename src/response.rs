@@ -0,0 +1,325 @@
+//! Responses carrying metadata alongside entities
+//!
+//! Service methods yield only the deserialized entity. Wrapping a call in
+//! [Github#with_response](../struct.Github.html#method.with_response) yields a
+//! [Response](struct.Response.html) instead, which also carries the status and headers GitHub
+//! responded with, e.g. to read an entity's `ETag` or the pagination links of a listing.
+//!
+//! ```no_run
+//! use hubcaps::Github;
+//!
+//! # async fn run(github: Github) -> hubcaps::Result<()> {
+//! let response = github
+//!     .with_response(|github| github.repo("octocat", "hello-world").get())
+//!     .await?;
+//! println!("{} etag {:?}", response.entity.full_name, response.etag());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The `ETag` and `Last-Modified` of a response can be used to make a
+//! [conditional](../struct.Github.html#method.conditional) request later on, which yields
+//! `Conditional::NotModified` without a body if the entity hasn't changed since. Requests
+//! answered this way don't count against the rate limit, which makes them well suited to
+//! polling for changes.
+//!
+//! ```no_run
+//! use hubcaps::response::{Conditional, Conditions};
+//! use hubcaps::Github;
+//!
+//! # async fn run(github: Github, etag: String) -> hubcaps::Result<()> {
+//! match github
+//!     .conditional(Conditions::etag(etag), |github| {
+//!         github.repo("octocat", "hello-world").get()
+//!     })
+//!     .await?
+//! {
+//!     Conditional::Modified(response) => println!("changed, new etag {:?}", response.etag()),
+//!     Conditional::NotModified => println!("unchanged"),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use http::header::{HeaderMap, ETAG, LAST_MODIFIED, LINK, WARNING};
+use http::StatusCode;
+use hyperx::header::{Link, RelationType};
+
+use crate::rate_limit::Budget;
+use crate::X_GITHUB_REQUEST_ID;
+
+const X_OAUTH_SCOPES: &str = "x-oauth-scopes";
+const X_ACCEPTED_OAUTH_SCOPES: &str = "x-accepted-oauth-scopes";
+
+/// An entity along with the status and headers of the response it was read from
+#[derive(Clone, Debug)]
+pub struct Response<T> {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub entity: T,
+}
+
+impl<T> Response<T> {
+    /// Transform the entity, keeping the response metadata
+    pub fn map<U, F>(self, f: F) -> Response<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Response {
+            status: self.status,
+            headers: self.headers,
+            entity: f(self.entity),
+        }
+    }
+
+    /// Return the entity, discarding the response metadata
+    pub fn into_entity(self) -> T {
+        self.entity
+    }
+
+    /// The `ETag` of the entity, usable for conditional requests
+    pub fn etag(&self) -> Option<&str> {
+        self.header(ETAG.as_str())
+    }
+
+    /// The `Last-Modified` date of the entity, usable for conditional requests
+    pub fn last_modified(&self) -> Option<&str> {
+        self.header(LAST_MODIFIED.as_str())
+    }
+
+    /// The unique id GitHub assigned the request, useful when contacting GitHub support
+    pub fn request_id(&self) -> Option<&str> {
+        self.header(X_GITHUB_REQUEST_ID)
+    }
+
+    /// The OAuth scopes granted to the credentials used to make the request
+    pub fn oauth_scopes(&self) -> Option<Vec<&str>> {
+        self.header(X_OAUTH_SCOPES).map(scopes)
+    }
+
+    /// The OAuth scopes the endpoint accepts
+    pub fn accepted_oauth_scopes(&self) -> Option<Vec<&str>> {
+        self.header(X_ACCEPTED_OAUTH_SCOPES).map(scopes)
+    }
+
+    /// Returns true if the response was served from the http cache without being revalidated
+    /// with GitHub, see [CacheMode](../http_cache/enum.CacheMode.html)
+    pub fn is_stale(&self) -> bool {
+        self.header(WARNING.as_str()).is_some_and(|warning| {
+            warning.starts_with("110") || warning.starts_with("111") || warning.starts_with("112")
+        })
+    }
+
+    /// The rate limit budget of the resource the request was accounted against
+    pub fn rate_limit(&self) -> Option<Budget> {
+        Budget::from_headers(&self.headers)
+    }
+
+    /// The pagination links of a listing
+    pub fn link(&self) -> Option<Link> {
+        self.header(LINK.as_str())
+            .and_then(|link| link.parse().ok())
+    }
+
+    /// The url of the next page of a listing
+    pub fn next_url(&self) -> Option<String> {
+        self.rel_url(RelationType::Next)
+    }
+
+    /// The url of the last page of a listing
+    pub fn last_url(&self) -> Option<String> {
+        self.rel_url(RelationType::Last)
+    }
+
+    fn rel_url(&self, rel: RelationType) -> Option<String> {
+        self.link()?.values().iter().find_map(|value| {
+            value
+                .rel()
+                .filter(|rels| rels.contains(&rel))
+                .map(|_| value.link().to_owned())
+        })
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+/// Preconditions for the GET requests of a
+/// [conditional](../struct.Github.html#method.conditional) call
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Conditions {
+    /// Sent as `If-None-Match`
+    pub etag: Option<String>,
+    /// Sent as `If-Modified-Since`
+    pub last_modified: Option<String>,
+}
+
+impl Conditions {
+    /// Request the entity only if its `ETag` no longer matches `etag`
+    pub fn etag<E>(etag: E) -> Self
+    where
+        E: Into<String>,
+    {
+        Conditions {
+            etag: Some(etag.into()),
+            last_modified: None,
+        }
+    }
+
+    /// Request the entity only if it was modified after `last_modified`, an HTTP date as
+    /// found in `Last-Modified` headers
+    pub fn last_modified<L>(last_modified: L) -> Self
+    where
+        L: Into<String>,
+    {
+        Conditions {
+            etag: None,
+            last_modified: Some(last_modified.into()),
+        }
+    }
+
+    /// Request the entity only if it changed since the given response
+    pub fn from_response<T>(response: &Response<T>) -> Self {
+        Conditions {
+            etag: response.etag().map(str::to_owned),
+            last_modified: response.last_modified().map(str::to_owned),
+        }
+    }
+}
+
+/// The outcome of a [conditional](../struct.Github.html#method.conditional) call
+#[derive(Clone, Debug)]
+pub enum Conditional<T> {
+    /// The entity changed, or no preconditions were given
+    Modified(Response<T>),
+    /// The entity hasn't changed since the preconditions were taken
+    NotModified,
+}
+
+impl<T> Conditional<T> {
+    /// Return the changed entity, if any
+    pub fn modified(self) -> Option<Response<T>> {
+        match self {
+            Conditional::Modified(response) => Some(response),
+            Conditional::NotModified => None,
+        }
+    }
+}
+
+fn scopes(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|scope| !scope.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{github, FakeTransport};
+    use crate::Error;
+    use futures::prelude::*;
+    use http::header::{HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+
+    #[test]
+    fn parses_metadata() {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &[
+            ("etag", "W/\"abc\""),
+            ("x-github-request-id", "0D00:1F2E"),
+            ("x-oauth-scopes", "repo, read:org"),
+            ("x-accepted-oauth-scopes", ""),
+            ("x-ratelimit-limit", "5000"),
+            ("x-ratelimit-remaining", "4999"),
+            ("x-ratelimit-reset", "1372700873"),
+            (
+                "link",
+                "<https://api.github.com/user/repos?page=2>; rel=\"next\", \
+                 <https://api.github.com/user/repos?page=5>; rel=\"last\"",
+            ),
+        ] {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        let response = Response {
+            status: StatusCode::OK,
+            headers,
+            entity: 1,
+        }
+        .map(|n| n + 1);
+
+        assert_eq!(response.etag(), Some("W/\"abc\""));
+        assert_eq!(response.last_modified(), None);
+        assert_eq!(response.request_id(), Some("0D00:1F2E"));
+        assert_eq!(response.oauth_scopes(), Some(vec!["repo", "read:org"]));
+        assert_eq!(response.accepted_oauth_scopes(), Some(vec![]));
+        assert_eq!(
+            response.rate_limit().map(|budget| budget.remaining),
+            Some(4999)
+        );
+        assert_eq!(
+            response.next_url().as_deref(),
+            Some("https://api.github.com/user/repos?page=2")
+        );
+        assert_eq!(
+            response.last_url().as_deref(),
+            Some("https://api.github.com/user/repos?page=5")
+        );
+        assert_eq!(
+            Conditions::from_response(&response),
+            Conditions::etag("W/\"abc\"")
+        );
+        assert_eq!(response.into_entity(), 2);
+    }
+
+    #[tokio::test]
+    async fn with_response_requires_a_request() {
+        let transport = FakeTransport::new(vec![(200, "{}")]);
+        let github = github(&transport);
+        let result = github
+            .with_response(|_| Box::pin(future::ready(Ok(()))))
+            .await;
+        match result {
+            Err(Error::NoResponse) => (),
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+        let response = github
+            .with_response(|github| github.get::<serde_json::Value>("/user"))
+            .await
+            .unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn conditional_requests() {
+        let transport = FakeTransport::new(vec![(304, ""), (200, r#"{"login":"octocat"}"#)]);
+        let github = github(&transport);
+        let conditions = Conditions {
+            etag: Some("\"abc\"".into()),
+            last_modified: Some("Tue, 07 Feb 2023 10:00:00 GMT".into()),
+        };
+        let unchanged = github
+            .conditional(conditions.clone(), |github| {
+                github.get::<serde_json::Value>("/user")
+            })
+            .await
+            .unwrap();
+        assert!(unchanged.modified().is_none());
+        let changed = github
+            .conditional(conditions, |github| {
+                github.get::<serde_json::Value>("/user")
+            })
+            .await
+            .unwrap()
+            .modified()
+            .unwrap();
+        assert_eq!(changed.entity["login"], "octocat");
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests[0].2[IF_NONE_MATCH], "\"abc\"");
+        assert_eq!(
+            requests[0].2[IF_MODIFIED_SINCE],
+            "Tue, 07 Feb 2023 10:00:00 GMT"
+        );
+    }
+}
@@ -0,0 +1,335 @@
+//! Retry policies for transient GitHub API failures
+//!
+//! By default a `Github` client makes exactly one attempt per request. A
+//! [RetryPolicy](struct.RetryPolicy.html) registered with
+//! [Github#set_retry_policy](../struct.Github.html#method.set_retry_policy) will
+//! retry requests which fail with a retryable status code or transport error,
+//! sleeping with exponential backoff and jitter between attempts.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use hubcaps::{Github, retry::RetryPolicy};
+//!
+//! # fn main() -> hubcaps::Result<()> {
+//! let mut github = Github::new("user-agent-name", None)?;
+//! github.set_retry_policy(
+//!     RetryPolicy::builder()
+//!         .max_attempts(5)
+//!         .initial_backoff(Duration::from_millis(250))
+//!         .build(),
+//! );
+//! # Ok(())
+//! # }
+//! ```
+use std::io;
+use std::time::Duration;
+
+use http::header::{HeaderMap, RETRY_AFTER};
+use http::{Method, StatusCode};
+use rand::Rng;
+
+use crate::Error;
+
+/// Kinds of transport failures a `RetryPolicy` may consider retryable
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RetryableError {
+    /// The request did not complete within the client's timeout
+    Timeout,
+    /// A connection to the server could not be established
+    Connect,
+    /// The connection failed while sending the request or reading the
+    /// response, for instance because it was reset by the peer
+    Request,
+}
+
+/// Describes if and how failed requests are retried
+///
+/// Only requests using one of the policy's methods are retried. By default
+/// these are the idempotent `GET`, `HEAD`, `PUT`, `DELETE` and `OPTIONS`
+/// methods so that `POST` and `PATCH` requests are never sent twice.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    statuses: Vec<StatusCode>,
+    errors: Vec<RetryableError>,
+    methods: Vec<Method>,
+}
+
+impl RetryPolicy {
+    /// Start building a policy from the defaults
+    pub fn builder() -> RetryPolicyBuilder {
+        RetryPolicyBuilder::default()
+    }
+
+    /// A policy which never retries. This is the default for new clients
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Maximum number of attempts, including the first one
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns true if the given status code should be retried for a request with this method
+    pub fn is_retryable_status(&self, method: &Method, status: StatusCode) -> bool {
+        self.methods.contains(method) && self.statuses.contains(&status)
+    }
+
+    /// Returns true if the given error should be retried for a request with this method
+    pub fn is_retryable_error(&self, method: &Method, error: &Error) -> bool {
+        self.methods.contains(method)
+            && retryable_error_kind(error)
+                .map(|kind| self.errors.contains(&kind))
+                .unwrap_or(false)
+    }
+
+    /// The duration to wait before the next attempt, given the number of attempts made so far.
+    ///
+    /// Backoff doubles with each attempt starting at the initial backoff and is capped at the
+    /// maximum backoff. When jitter is enabled a random duration between zero and the computed
+    /// backoff is used instead.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(31);
+        let backoff = self
+            .initial_backoff
+            .checked_mul(1 << exponent)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+        if self.jitter && backoff > Duration::from_millis(0) {
+            let millis = backoff.as_millis() as u64;
+            Duration::from_millis(rand::thread_rng().gen_range(0, millis + 1))
+        } else {
+            backoff
+        }
+    }
+
+    /// The delay before retrying a request which failed with a retryable status,
+    /// honoring a `Retry-After` header when the server sent one
+    pub(crate) fn status_backoff(&self, attempts: u32, headers: &HeaderMap) -> Duration {
+        let backoff = self.backoff(attempts);
+        match retry_after(headers) {
+            Some(retry_after) => retry_after.min(self.max_backoff).max(backoff),
+            None => backoff,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            jitter: true,
+            statuses: vec![
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            errors: vec![
+                RetryableError::Timeout,
+                RetryableError::Connect,
+                RetryableError::Request,
+            ],
+            methods: vec![
+                Method::GET,
+                Method::HEAD,
+                Method::PUT,
+                Method::DELETE,
+                Method::OPTIONS,
+            ],
+        }
+    }
+}
+
+/// A mutable retry policy builder
+///
+/// The builder starts out with the defaults: 3 attempts, a backoff of 500ms doubling up to 30s
+/// with jitter, retrying 502, 503 and 504 responses, timeouts and connection failures for
+/// idempotent methods.
+#[derive(Default)]
+pub struct RetryPolicyBuilder(RetryPolicy);
+
+impl RetryPolicyBuilder {
+    /// Maximum number of attempts, including the first one
+    pub fn max_attempts(&mut self, max_attempts: u32) -> &mut Self {
+        self.0.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry
+    pub fn initial_backoff(&mut self, backoff: Duration) -> &mut Self {
+        self.0.initial_backoff = backoff;
+        self
+    }
+
+    /// Upper bound for the delay between two attempts
+    pub fn max_backoff(&mut self, backoff: Duration) -> &mut Self {
+        self.0.max_backoff = backoff;
+        self
+    }
+
+    /// Randomize delays between attempts to avoid synchronized retries across clients
+    pub fn jitter(&mut self, jitter: bool) -> &mut Self {
+        self.0.jitter = jitter;
+        self
+    }
+
+    /// Response status codes which are retried
+    pub fn statuses(&mut self, statuses: Vec<StatusCode>) -> &mut Self {
+        self.0.statuses = statuses;
+        self
+    }
+
+    /// Transport failures which are retried
+    pub fn errors(&mut self, errors: Vec<RetryableError>) -> &mut Self {
+        self.0.errors = errors;
+        self
+    }
+
+    /// Request methods which are retried. Only add non idempotent methods like `POST`
+    /// if the requests you make are safe to repeat
+    pub fn methods(&mut self, methods: Vec<Method>) -> &mut Self {
+        self.0.methods = methods;
+        self
+    }
+
+    /// The policy configured so far
+    pub fn build(&self) -> RetryPolicy {
+        self.0.clone()
+    }
+}
+
+fn retryable_error_kind(error: &Error) -> Option<RetryableError> {
+    match error {
+        Error::Reqwest(err) if err.is_timeout() => Some(RetryableError::Timeout),
+        Error::Reqwest(err) if err.is_connect() => Some(RetryableError::Connect),
+        Error::Reqwest(err) if err.is_request() || err.is_body() => Some(RetryableError::Request),
+        // custom transports may report connection failures as IO errors, unlike middleware
+        // or replayed cassettes whose errors won't go away by trying again
+        Error::IO(err) => match err.kind() {
+            io::ErrorKind::TimedOut => Some(RetryableError::Timeout),
+            io::ErrorKind::ConnectionRefused => Some(RetryableError::Connect),
+            io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::UnexpectedEof => Some(RetryableError::Request),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parse a `Retry-After` header given in seconds
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{github, FakeTransport};
+    use http::header::HeaderValue;
+
+    #[test]
+    fn default_policy_retries_idempotent_methods_only() {
+        let policy = RetryPolicy::default();
+        assert!(policy.is_retryable_status(&Method::GET, StatusCode::BAD_GATEWAY));
+        assert!(policy.is_retryable_status(&Method::DELETE, StatusCode::GATEWAY_TIMEOUT));
+        assert!(!policy.is_retryable_status(&Method::POST, StatusCode::BAD_GATEWAY));
+        assert!(!policy.is_retryable_status(&Method::GET, StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn retries_transport_io_errors_only() {
+        let policy = RetryPolicy::default();
+        let error = |kind| Error::IO(io::Error::new(kind, "failed"));
+        assert!(policy.is_retryable_error(&Method::GET, &error(io::ErrorKind::ConnectionReset)));
+        assert!(policy.is_retryable_error(&Method::GET, &error(io::ErrorKind::TimedOut)));
+        assert!(!policy.is_retryable_error(&Method::GET, &error(io::ErrorKind::InvalidData)));
+        assert!(!policy.is_retryable_error(&Method::GET, &error(io::ErrorKind::PermissionDenied)));
+        assert!(!policy.is_retryable_error(&Method::POST, &error(io::ErrorKind::ConnectionReset)));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let policy = RetryPolicy::builder()
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(350))
+            .jitter(false)
+            .build();
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+        assert_eq!(policy.backoff(40), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn jittered_backoff_is_bounded() {
+        let policy = RetryPolicy::builder()
+            .initial_backoff(Duration::from_millis(100))
+            .build();
+        for _ in 0..100 {
+            assert!(policy.backoff(2) <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn status_backoff_honors_retry_after() {
+        let policy = RetryPolicy::builder()
+            .initial_backoff(Duration::from_millis(100))
+            .jitter(false)
+            .build();
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("2"));
+        assert_eq!(policy.status_backoff(1, &headers), Duration::from_secs(2));
+        assert_eq!(
+            policy.status_backoff(1, &HeaderMap::new()),
+            Duration::from_millis(100)
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures() {
+        let transport = FakeTransport::new(vec![
+            (502, "bad gateway"),
+            (503, "unavailable"),
+            (200, "{}"),
+        ]);
+        let mut github = github(&transport);
+        github.set_retry_policy(
+            RetryPolicy::builder()
+                .initial_backoff(Duration::from_millis(1))
+                .jitter(false)
+                .build(),
+        );
+        github.get::<serde_json::Value>("/user").await.unwrap();
+        assert_eq!(transport.requests.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_non_idempotent_requests() {
+        let transport = FakeTransport::new(vec![(502, r#"{"message":"Bad Gateway"}"#)]);
+        let mut github = github(&transport);
+        github.set_retry_policy(RetryPolicy::default());
+        let result = github
+            .post::<serde_json::Value>("/repos/o/r/issues", b"{}".to_vec())
+            .await;
+        match result {
+            Err(Error::Fault { code, .. }) => assert_eq!(code, StatusCode::BAD_GATEWAY),
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+        assert_eq!(transport.requests.lock().unwrap().len(), 1);
+    }
+}
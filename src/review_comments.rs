@@ -2,7 +2,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Timestamp};
 
 /// A structure for interfacing with a review comments
 pub struct ReviewComments {
@@ -67,8 +67,8 @@ pub struct ReviewComment {
     pub original_commit_id: String,
     pub user: User,
     pub body: String,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
     pub html_url: String,
     pub pull_request_url: String,
 }
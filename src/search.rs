@@ -8,7 +8,7 @@ use url::{self, form_urlencoded};
 
 use crate::labels::Label;
 use crate::users::User;
-use crate::{unfold, Future, Github, SortDirection, Stream};
+use crate::{unfold, Future, Github, SortDirection, Stream, Timestamp};
 
 mod repos;
 
@@ -70,7 +70,7 @@ impl Search {
     where
         D: DeserializeOwned + 'static + Send,
     {
-        unfold(self.github.clone(), self.github.get_pages(url), items)
+        unfold(self.github.clone(), url, items)
     }
 
     fn search<D>(&self, url: &str) -> Future<SearchResult<D>>
@@ -207,9 +207,9 @@ pub struct IssuesItem {
     pub assignee: Option<User>,
     pub assignees: Vec<User>,
     pub comments: u64,
-    pub created_at: String,
-    pub updated_at: String,
-    pub closed_at: Option<String>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    pub closed_at: Option<Timestamp>,
     pub pull_request: Option<PullRequestInfo>,
     pub body: Option<String>,
 }
@@ -6,7 +6,7 @@ use url::form_urlencoded;
 
 use super::{Search, SearchResult};
 use crate::users::User;
-use crate::{Future, SortDirection, Stream};
+use crate::{Future, SortDirection, Stream, Timestamp};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ReposSort {
@@ -172,9 +172,9 @@ pub struct ReposItem {
     pub labels_url: String,
     pub releases_url: String,
     pub deployments_url: String,
-    pub created_at: String,
-    pub updated_at: String,
-    pub pushed_at: String,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    pub pushed_at: Timestamp,
     pub git_url: String,
     pub ssh_url: String,
     pub clone_url: String,
@@ -201,6 +201,7 @@ pub struct ReposItem {
     pub score: f64,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct License {
     key: String,
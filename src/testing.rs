@@ -0,0 +1,69 @@
+//! Helpers for unit tests exercising a `Github` client without a network
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use futures::prelude::*;
+use http::header::HeaderMap;
+use http::Method;
+
+use crate::transport::Transport;
+use crate::{Credentials, Error, Future, Github};
+
+/// A transport answering requests with canned responses
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FakeTransport {
+    responses: Arc<Mutex<VecDeque<(u16, &'static str)>>>,
+    headers: Arc<Mutex<VecDeque<HeaderMap>>>,
+    pub(crate) requests: Arc<Mutex<Vec<(Method, String, HeaderMap)>>>,
+    pub(crate) bodies: Arc<Mutex<Vec<Option<Vec<u8>>>>>,
+}
+
+impl FakeTransport {
+    pub(crate) fn new(responses: Vec<(u16, &'static str)>) -> Self {
+        FakeTransport {
+            responses: Arc::new(Mutex::new(responses.into())),
+            ..FakeTransport::default()
+        }
+    }
+
+    /// Answer requests with the given headers, in the order of the responses
+    pub(crate) fn with_headers(self, headers: Vec<HeaderMap>) -> Self {
+        *self.headers.lock().unwrap() = headers.into();
+        self
+    }
+}
+
+impl Transport for FakeTransport {
+    fn send(&self, request: http::Request<Option<Vec<u8>>>) -> Future<http::Response<Vec<u8>>> {
+        self.requests.lock().unwrap().push((
+            request.method().clone(),
+            request.uri().to_string(),
+            request.headers().clone(),
+        ));
+        self.bodies.lock().unwrap().push(request.body().clone());
+        let (status, body) = self
+            .responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .expect("no more responses");
+        let headers = self.headers.lock().unwrap().pop_front().unwrap_or_default();
+        Box::pin(future::ready(
+            http::Response::builder()
+                .status(status)
+                .body(body.as_bytes().to_vec())
+                .map(|mut response| {
+                    *response.headers_mut() = headers;
+                    response
+                })
+                .map_err(Error::from),
+        ))
+    }
+}
+
+/// A client authenticated with a token, sending requests through the given transport
+pub(crate) fn github(transport: &FakeTransport) -> Github {
+    let mut github = Github::new("agent", Credentials::Token("secret".into())).unwrap();
+    github.set_transport(transport.clone());
+    github
+}
@@ -0,0 +1,440 @@
+//! Points in time, as found in representations
+use std::convert::TryFrom;
+use std::error::Error as StdError;
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+#[cfg(not(feature = "chrono"))]
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// A point in time, as found in representations and taken by options filtering by time
+///
+/// GitHub formats these as RFC 3339 timestamps like `2011-01-26T19:01:12Z`, which is what
+/// parsing a `Timestamp` expects and displaying one produces. Strings which aren't timestamps
+/// are rejected when parsing, rather than sent along to GitHub.
+///
+/// ```
+/// use hubcaps::Timestamp;
+///
+/// let since: Timestamp = "2011-01-26T19:01:12Z".parse().unwrap();
+/// assert_eq!(since.to_string(), "2011-01-26T19:01:12Z");
+/// assert!("yesterday".parse::<Timestamp>().is_err());
+/// ```
+///
+/// Timestamps convert from and to `SystemTime`s. With the `chrono` feature enabled, they are
+/// backed by a `chrono::DateTime<Utc>` and convert from and to those too.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(Inner);
+
+#[cfg(feature = "chrono")]
+type Inner = chrono::DateTime<chrono::Utc>;
+
+/// Seconds and nanoseconds since the unix epoch
+#[cfg(not(feature = "chrono"))]
+type Inner = (i64, u32);
+
+/// The error returned when parsing a string which isn't an RFC 3339 timestamp
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseTimestampError(String);
+
+impl fmt::Display for ParseTimestampError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} isn't an RFC 3339 timestamp", self.0)
+    }
+}
+
+impl StdError for ParseTimestampError {}
+
+impl Timestamp {
+    /// The timestamp as a point in system time
+    #[cfg(not(feature = "chrono"))]
+    pub fn to_system_time(&self) -> SystemTime {
+        let (seconds, nanos) = self.0;
+        let since_epoch = Duration::from_secs(seconds.unsigned_abs());
+        let since_epoch = if seconds < 0 {
+            UNIX_EPOCH - since_epoch
+        } else {
+            UNIX_EPOCH + since_epoch
+        };
+        since_epoch + Duration::from_nanos(u64::from(nanos))
+    }
+
+    /// The timestamp as a point in system time
+    #[cfg(feature = "chrono")]
+    pub fn to_system_time(&self) -> SystemTime {
+        SystemTime::from(self.0)
+    }
+
+    /// The timestamp as a `chrono::DateTime<Utc>`
+    #[cfg(feature = "chrono")]
+    pub fn to_datetime(&self) -> chrono::DateTime<chrono::Utc> {
+        self.0
+    }
+}
+
+#[cfg(not(feature = "chrono"))]
+impl FromStr for Timestamp {
+    type Err = ParseTimestampError;
+
+    fn from_str(timestamp: &str) -> Result<Self, Self::Err> {
+        parse(timestamp)
+            .map(Timestamp)
+            .ok_or_else(|| ParseTimestampError(timestamp.to_owned()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromStr for Timestamp {
+    type Err = ParseTimestampError;
+
+    fn from_str(timestamp: &str) -> Result<Self, Self::Err> {
+        chrono::DateTime::parse_from_rfc3339(timestamp)
+            .map(|datetime| Timestamp(datetime.with_timezone(&chrono::Utc)))
+            .map_err(|_| ParseTimestampError(timestamp.to_owned()))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Timestamp {
+    type Error = ParseTimestampError;
+
+    fn try_from(timestamp: &'a str) -> Result<Self, Self::Error> {
+        timestamp.parse()
+    }
+}
+
+impl TryFrom<String> for Timestamp {
+    type Error = ParseTimestampError;
+
+    fn try_from(timestamp: String) -> Result<Self, Self::Error> {
+        timestamp.parse()
+    }
+}
+
+#[cfg(not(feature = "chrono"))]
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (seconds, nanos) = self.0;
+        let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+        let seconds = seconds.rem_euclid(86_400);
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )?;
+        // as many digits of the fraction as `chrono::SecondsFormat::AutoSi` writes
+        if nanos == 0 {
+        } else if nanos % 1_000_000 == 0 {
+            write!(f, ".{:03}", nanos / 1_000_000)?;
+        } else if nanos % 1_000 == 0 {
+            write!(f, ".{:06}", nanos / 1_000)?;
+        } else {
+            write!(f, ".{:09}", nanos)?;
+        }
+        f.write_str("Z")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true))
+    }
+}
+
+impl fmt::Debug for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Timestamp({})", self)
+    }
+}
+
+impl From<Timestamp> for String {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.to_string()
+    }
+}
+
+#[cfg(not(feature = "chrono"))]
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        Timestamp(match time.duration_since(UNIX_EPOCH) {
+            Ok(since_epoch) => (since_epoch.as_secs() as i64, since_epoch.subsec_nanos()),
+            Err(err) => {
+                let before_epoch = err.duration();
+                let (seconds, nanos) = (before_epoch.as_secs() as i64, before_epoch.subsec_nanos());
+                if nanos == 0 {
+                    (-seconds, 0)
+                } else {
+                    (-seconds - 1, 1_000_000_000 - nanos)
+                }
+            }
+        })
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        Timestamp(time.into())
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.to_system_time()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::Utc>> for Timestamp {
+    fn from(datetime: chrono::DateTime<chrono::Utc>) -> Self {
+        Timestamp(datetime)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<Timestamp> for chrono::DateTime<chrono::Utc> {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.0
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Parse an RFC 3339 timestamp like `2011-01-26T19:01:12Z` or
+/// `2011-01-26T20:01:12.5+01:00` into seconds and nanoseconds since the unix epoch
+#[cfg(not(feature = "chrono"))]
+fn parse(timestamp: &str) -> Option<Inner> {
+    let bytes = timestamp.as_bytes();
+    let field = |range| number(timestamp, range);
+    if bytes.len() < 20
+        || (bytes[4], bytes[7], bytes[13], bytes[16]) != (b'-', b'-', b':', b':')
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+    {
+        return None;
+    }
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hour, minute, second) = (field(11..13)?, field(14..16)?, field(17..19)?);
+    let days = days_from_civil(year, month, day);
+    // dates like february 30th don't survive the round trip
+    if !(1..=12).contains(&month) || civil_from_days(days) != (year, month, day) {
+        return None;
+    }
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    let mut rest = &timestamp[19..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return None;
+        }
+        // digits beyond nanoseconds are dropped
+        nanos = fraction[..digits]
+            .bytes()
+            .chain(std::iter::repeat(b'0'))
+            .take(9)
+            .fold(0, |nanos, digit| nanos * 10 + u32::from(digit - b'0'));
+        rest = &fraction[digits..];
+    }
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.as_bytes().first()? {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            if rest.len() != 6 || rest.as_bytes()[3] != b':' {
+                return None;
+            }
+            let (hours, minutes) = (number(rest, 1..3)?, number(rest, 4..6)?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            sign * (hours * 3600 + minutes * 60)
+        }
+    };
+    Some((
+        days * 86_400 + hour * 3600 + minute * 60 + second - offset,
+        nanos,
+    ))
+}
+
+/// The number in `range` of `text`, if it is made of ascii digits only
+#[cfg(not(feature = "chrono"))]
+fn number(text: &str, range: std::ops::Range<usize>) -> Option<i64> {
+    let digits = text.get(range)?;
+    if digits.bytes().all(|b| b.is_ascii_digit()) {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Days since the unix epoch of a date in the proleptic gregorian calendar
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+#[cfg(any(not(feature = "chrono"), test))]
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The `(year, month, day)` of a number of days since the unix epoch, the inverse of
+/// `days_from_civil`
+#[cfg(any(not(feature = "chrono"), test))]
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn timestamp(text: &str) -> Timestamp {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn timestamps_roundtrip() {
+        let token: crate::app::AccessToken =
+            serde_json::from_str(r#"{"token":"t","expires_at":"2016-07-11T22:14:10Z"}"#).unwrap();
+        assert_eq!(token.expires_at.to_string(), "2016-07-11T22:14:10Z");
+        assert_eq!(
+            serde_json::to_value(token.expires_at).unwrap(),
+            serde_json::json!("2016-07-11T22:14:10Z")
+        );
+        assert!(serde_json::from_str::<Timestamp>(r#""yesterday""#).is_err());
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(
+            timestamp("2011-01-26T20:01:12+01:00"),
+            timestamp("2011-01-26T19:01:12Z")
+        );
+        assert_eq!(
+            timestamp("2011-01-26T19:01:12.5Z").to_string(),
+            "2011-01-26T19:01:12.500Z"
+        );
+        assert!(timestamp("2011-01-26T19:01:12Z") < timestamp("2011-01-26T19:01:12.5Z"));
+        for invalid in &[
+            "yesterday",
+            "",
+            "2011-01-26",
+            "2011-01-26T19:01:12",
+            "2011-02-29T00:00:00Z",
+            "2011-13-01T00:00:00Z",
+            "2011-01-26T24:00:00Z",
+            "2011-01-26T19:01:12.Z",
+            "2011-01-26T19:01:12+1:00",
+        ] {
+            assert_eq!(
+                invalid.parse::<Timestamp>(),
+                Err(ParseTimestampError(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn converts_timestamps() {
+        let seconds = |text: &str| {
+            timestamp(text)
+                .to_system_time()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+        };
+        assert_eq!(seconds("2011-01-26T19:01:12Z").as_secs(), 1_296_068_472);
+        assert_eq!(seconds("2000-02-29T23:59:59Z").as_secs(), 951_868_799);
+        assert_eq!(seconds("2000-02-29T23:59:59.25Z").subsec_millis(), 250);
+        assert_eq!(
+            Timestamp::from(UNIX_EPOCH + Duration::from_secs(1_296_068_472)).to_string(),
+            "2011-01-26T19:01:12Z"
+        );
+        let before_epoch = Timestamp::from(UNIX_EPOCH - Duration::from_millis(500));
+        assert_eq!(before_epoch.to_string(), "1969-12-31T23:59:59.500Z");
+        assert_eq!(
+            before_epoch.to_system_time(),
+            UNIX_EPOCH - Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn converts_timestamps_to_datetimes() {
+        use chrono::TimeZone;
+        let datetime = chrono::Utc
+            .with_ymd_and_hms(2011, 1, 26, 19, 1, 12)
+            .unwrap();
+        assert_eq!(Timestamp::from(datetime), timestamp("2011-01-26T19:01:12Z"));
+        assert_eq!(
+            timestamp("2011-01-26T20:01:12+01:00").to_datetime(),
+            datetime
+        );
+    }
+
+    #[test]
+    fn converts_civil_dates() {
+        for &(date, days) in &[
+            ((1969, 12, 31), -1),
+            ((1970, 1, 1), 0),
+            ((1900, 2, 28), -25_509),
+            ((1900, 3, 1), -25_508),
+            ((2000, 2, 29), 11_016),
+            ((2000, 3, 1), 11_017),
+            ((2024, 12, 31), 20_088),
+            ((2025, 1, 1), 20_089),
+            ((2100, 2, 28), 47_540),
+            ((2100, 3, 1), 47_541),
+        ] {
+            assert_eq!(days_from_civil(date.0, date.1, date.2), days, "{:?}", date);
+            assert_eq!(civil_from_days(days), date);
+        }
+    }
+}
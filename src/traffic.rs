@@ -3,7 +3,7 @@ use std::fmt;
 
 use serde::Deserialize;
 
-use crate::{Future, Github};
+use crate::{Future, Github, Timestamp};
 
 /// Describes types of breakdowns of the data for views or clones
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -109,7 +109,7 @@ pub struct Clones {
 
 #[derive(Debug, Deserialize)]
 pub struct DataPoint {
-    pub timestamp: String,
+    pub timestamp: Timestamp,
     pub count: u32,
     pub uniques: u32,
 }
@@ -0,0 +1,128 @@
+//! Pluggable HTTP transports
+//!
+//! A `Github` client dispatches every request through a [Transport](trait.Transport.html).
+//! By default this is a [ReqwestTransport](struct.ReqwestTransport.html) wrapping a
+//! `reqwest::Client`, but any implementation may be provided with
+//! [Github#set_transport](../struct.Github.html#method.set_transport), for instance to use a
+//! custom connector, to instrument requests or to answer requests in process in unit tests.
+use std::fmt::Debug;
+
+use futures::prelude::*;
+use http::{Request, Response};
+use log::debug;
+use reqwest::{Body, Client, Url};
+
+use crate::{Error, Future};
+
+pub type BoxedTransport = Box<dyn Transport + Send + Sync>;
+
+/// Sends requests prepared by a `Github` client
+///
+/// Requests carry the complete url, including query parameters, and all headers, including
+/// authentication. A request body of `None` means no body should be sent, which is different
+/// from an empty one. Implementations resolve with the response status, headers and the
+/// complete response body. Responses with error statuses should resolve successfully, they are
+/// interpreted by the client.
+pub trait Transport: TransportClone + Debug {
+    fn send(&self, request: Request<Option<Vec<u8>>>) -> Future<Response<Vec<u8>>>;
+}
+
+impl Clone for BoxedTransport {
+    fn clone(&self) -> Self {
+        (**self).box_clone()
+    }
+}
+
+impl Transport for BoxedTransport {
+    fn send(&self, request: Request<Option<Vec<u8>>>) -> Future<Response<Vec<u8>>> {
+        (**self).send(request)
+    }
+}
+
+/// The default transport, backed by a `reqwest::Client`
+#[derive(Clone, Debug)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: Client) -> Self {
+        ReqwestTransport { client }
+    }
+}
+
+impl From<Client> for ReqwestTransport {
+    fn from(client: Client) -> Self {
+        ReqwestTransport::new(client)
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: Request<Option<Vec<u8>>>) -> Future<Response<Vec<u8>>> {
+        let (parts, body) = request.into_parts();
+        let url = match Url::parse(&parts.uri.to_string()) {
+            Ok(url) => url,
+            Err(err) => return Box::pin(future::err(err.into())),
+        };
+        let mut req = self
+            .client
+            .request(parts.method, url)
+            .headers(parts.headers);
+        if let Some(body) = body {
+            req = req.body(Body::from(body));
+        }
+        debug!("Request: {:?}", &req);
+        Box::pin(
+            req.send()
+                .map_err(Error::from)
+                .and_then(|response| async move {
+                    let mut builder = Response::builder()
+                        .status(response.status())
+                        .version(response.version());
+                    if let Some(headers) = builder.headers_mut() {
+                        *headers = response.headers().clone();
+                    }
+                    let body = response.bytes().await?;
+                    builder.body(body.to_vec()).map_err(Error::from)
+                }),
+        )
+    }
+}
+
+// Separate to provide a blanket implementation for `T: Transport + Clone`
+// https://stackoverflow.com/a/30353928/463761
+#[doc(hidden)]
+pub trait TransportClone {
+    #[doc(hidden)]
+    fn box_clone(&self) -> BoxedTransport;
+}
+
+impl<T> TransportClone for T
+where
+    T: 'static + Transport + Clone + Send + Sync,
+{
+    fn box_clone(&self) -> BoxedTransport {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{github, FakeTransport};
+    use http::header::{AUTHORIZATION, USER_AGENT};
+    use http::Method;
+
+    #[tokio::test]
+    async fn dispatches_requests_through_transport() {
+        let transport = FakeTransport::new(vec![(200, r#"{"login":"octocat"}"#)]);
+        let user: serde_json::Value = github(&transport).get("/user").await.unwrap();
+        assert_eq!(user["login"], "octocat");
+
+        let requests = transport.requests.lock().unwrap();
+        let (method, uri, headers) = &requests[0];
+        assert_eq!(method, Method::GET);
+        assert_eq!(uri, "https://api.github.com/user");
+        assert_eq!(headers[USER_AGENT], "agent");
+        assert_eq!(headers[AUTHORIZATION], "token secret");
+    }
+}
@@ -1,5 +1,5 @@
 //! Users interface
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, Stream, Timestamp};
 use serde::{Deserialize, Serialize};
 
 /// User information
@@ -57,8 +57,8 @@ pub struct AuthenticatedUser {
     pub public_gists: u64,
     pub followers: u64,
     pub following: u64,
-    pub created_at: String, // TODO: change to `DateTime`?
-    pub updated_at: String, // TODO: change to `DateTime`?
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
 }
 
 #[derive(Debug, Deserialize)]
@@ -3,7 +3,7 @@
 use serde::Deserialize;
 
 use crate::repositories::Repo;
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, Stream, Timestamp};
 
 pub struct Watching {
     github: Github,
@@ -77,7 +77,7 @@ pub struct Subscription {
     pub subscribed: bool,
     pub ignored: bool,
     pub reason: Option<String>,
-    pub created_at: String,
+    pub created_at: Timestamp,
     pub url: String,
     pub repository_url: String,
 }
@@ -0,0 +1,35 @@
+#![cfg(all(feature = "blocking", feature = "fake"))]
+
+use hubcaps::blocking::Github;
+use hubcaps::fake::FakeGithub;
+use hubcaps::issues::{IssueListOptions, IssueOptions};
+use hubcaps::Result;
+
+#[test]
+fn iterates_issues_without_a_runtime() -> Result<()> {
+    // the fake server needs a runtime of its own, separate from the blocking client's
+    let runtime = tokio::runtime::Runtime::new()?;
+    let fake = runtime.enter(FakeGithub::start)?;
+    fake.create_repo("octocat", "hello-world");
+
+    let github = Github::host(fake.url(), "hubcaps", None)?;
+    let issues = github.repo("octocat", "hello-world").issues();
+    for title in &["one", "two", "three"] {
+        issues.create(&IssueOptions::new(
+            *title,
+            None::<String>,
+            None::<String>,
+            None,
+            Vec::<String>::new(),
+        ))?;
+    }
+    issues.get(2).labels().add(vec!["bug"])?;
+
+    let titles = issues
+        .iter(&IssueListOptions::builder().per_page(1).build())
+        .map(|issue| issue.map(|issue| issue.title))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(titles, vec!["three", "two", "one"]);
+    assert_eq!(issues.get(2).get()?.labels[0].name, "bug");
+    Ok(())
+}
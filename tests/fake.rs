@@ -0,0 +1,314 @@
+#![cfg(feature = "fake")]
+
+use futures::prelude::*;
+use hubcaps::comments::{CommentListOptions, CommentOptions};
+use hubcaps::fake::FakeGithub;
+use hubcaps::issues::{Issue, IssueListOptions, IssueOptions, State};
+use hubcaps::page::Cursor;
+use hubcaps::pulls::{PullEditOptions, PullListOptions, PullOptions};
+use hubcaps::statuses::{State as StatusState, StatusOptions};
+use hubcaps::{Error, Github, Result};
+
+fn github(fake: &FakeGithub) -> Result<Github> {
+    Github::host(fake.url(), "hubcaps", None)
+}
+
+#[tokio::test]
+async fn tracks_issue_state() -> Result<()> {
+    let fake = FakeGithub::start()?;
+    fake.create_repo("octocat", "hello-world");
+    let repo = github(&fake)?.repo("octocat", "hello-world");
+
+    let issue = repo
+        .issues()
+        .create(&IssueOptions::new(
+            "first",
+            Some("body"),
+            None::<String>,
+            None,
+            vec!["bug"],
+        ))
+        .await?;
+    assert_eq!(issue.number, 1);
+    assert_eq!(issue.labels[0].name, "bug");
+
+    let issue = repo.issues().get(1);
+    issue.labels().add(vec!["triage"]).await?;
+    issue.labels().remove("bug").await?;
+    issue
+        .comments()
+        .create(&CommentOptions {
+            body: "looking into it".into(),
+        })
+        .await?;
+    issue
+        .edit(&IssueOptions::new(
+            "first",
+            None::<String>,
+            None::<String>,
+            None,
+            vec!["triage"],
+        ))
+        .await?;
+
+    let issue = repo.issues().get(1).get().await?;
+    assert_eq!(issue.comments, 1);
+    assert_eq!(
+        issue.labels.into_iter().map(|l| l.name).collect::<Vec<_>>(),
+        vec!["triage"]
+    );
+    let comments = repo
+        .issues()
+        .get(1)
+        .comments()
+        .list(&CommentListOptions::builder().build())
+        .await?;
+    assert_eq!(comments[0].body, "looking into it");
+
+    let labels = repo.labels().list().await?;
+    assert_eq!(
+        labels.into_iter().map(|l| l.name).collect::<Vec<_>>(),
+        vec!["bug", "triage"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn paginates_issue_listings() -> Result<()> {
+    let fake = FakeGithub::start()?;
+    fake.create_repo("octocat", "hello-world");
+    let repo = github(&fake)?.repo("octocat", "hello-world");
+    for title in &["one", "two", "three"] {
+        repo.issues()
+            .create(&IssueOptions::new(
+                *title,
+                None::<String>,
+                None::<String>,
+                None,
+                Vec::<String>::new(),
+            ))
+            .await?;
+    }
+
+    let options = IssueListOptions::builder().per_page(2).build();
+    assert_eq!(repo.issues().list(&options).await?.len(), 2);
+    let titles = repo
+        .issues()
+        .iter(&options)
+        .map_ok(|issue| issue.title)
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(titles, vec!["three", "two", "one"]);
+
+    let closed = IssueListOptions::builder().state(State::Closed).build();
+    assert!(repo.issues().list(&closed).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn prefetches_pages_concurrently() -> Result<()> {
+    let fake = FakeGithub::start()?;
+    fake.create_repo("octocat", "hello-world");
+    let mut github = github(&fake)?;
+    github.set_page_concurrency(3);
+    let repo = github.repo("octocat", "hello-world");
+    for number in 1..=9 {
+        repo.issues()
+            .create(&IssueOptions::new(
+                number.to_string(),
+                None::<String>,
+                None::<String>,
+                None,
+                Vec::<String>::new(),
+            ))
+            .await?;
+    }
+
+    let numbers = repo
+        .issues()
+        .iter(&IssueListOptions::builder().per_page(2).build())
+        .map_ok(|issue| issue.number)
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(numbers, (1..=9).rev().collect::<Vec<_>>());
+    Ok(())
+}
+
+#[tokio::test]
+async fn resumes_streams_from_cursors() -> Result<()> {
+    let fake = FakeGithub::start()?;
+    fake.create_repo("octocat", "hello-world");
+    let mut github = github(&fake)?;
+    let repo = github.repo("octocat", "hello-world");
+    for number in 1..=5 {
+        repo.issues()
+            .create(&IssueOptions::new(
+                number.to_string(),
+                None::<String>,
+                None::<String>,
+                None,
+                Vec::<String>::new(),
+            ))
+            .await?;
+    }
+
+    let processed = repo
+        .issues()
+        .iter_resumable(&IssueListOptions::builder().per_page(2).build())
+        .take(3)
+        .try_collect::<Vec<_>>()
+        .await?;
+    let (cursor, issue) = processed.last().unwrap();
+    assert_eq!(issue.number, 3);
+    let saved = serde_json::to_string(cursor).unwrap();
+
+    for concurrency in &[1, 2] {
+        github.set_page_concurrency(*concurrency);
+        let cursor: Cursor = serde_json::from_str(&saved).unwrap();
+        let numbers = github
+            .resume_stream::<Issue>(&cursor)
+            .map_ok(|(_, issue)| issue.number)
+            .try_collect::<Vec<_>>()
+            .await?;
+        assert_eq!(numbers, vec![2, 1]);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn navigates_issue_pages() -> Result<()> {
+    let fake = FakeGithub::start()?;
+    fake.create_repo("octocat", "hello-world");
+    let repo = github(&fake)?.repo("octocat", "hello-world");
+    for title in &["one", "two", "three", "four", "five"] {
+        repo.issues()
+            .create(&IssueOptions::new(
+                *title,
+                None::<String>,
+                None::<String>,
+                None,
+                Vec::<String>::new(),
+            ))
+            .await?;
+    }
+
+    let first = repo
+        .issues()
+        .page(&IssueListOptions::builder().per_page(2).build())
+        .await?;
+    assert_eq!(first.number(), 1);
+    assert_eq!(first.last_number(), Some(3));
+    assert_eq!(first.total_estimate(), Some(6));
+    assert!(first.fetch_prev().is_none());
+
+    let last = first.fetch_last().unwrap().await?;
+    assert_eq!(last.number(), 3);
+    assert_eq!(last.total_estimate(), Some(5));
+    assert!(last.fetch_next().is_none());
+    let titles = |page: &hubcaps::page::Page<hubcaps::issues::Issue>| {
+        page.items
+            .iter()
+            .map(|issue| issue.title.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(titles(&last), vec!["one"]);
+
+    let second = last.fetch_prev().unwrap().await?;
+    assert_eq!(titles(&second), vec!["three", "two"]);
+    assert_eq!(titles(&second.fetch_page(1).await?), vec!["five", "four"]);
+    assert!(second.fetch_page(4).await?.items.is_empty());
+
+    let only = repo
+        .issues()
+        .page(&IssueListOptions::builder().per_page(10).build())
+        .await?
+        .fetch_page(0)
+        .await?;
+    assert_eq!(only.number(), 1);
+    assert_eq!(only.total_estimate(), Some(5));
+    Ok(())
+}
+
+#[tokio::test]
+async fn exposes_response_metadata() -> Result<()> {
+    let fake = FakeGithub::start()?;
+    fake.create_repo("octocat", "hello-world");
+    let github = github(&fake)?;
+    for title in &["one", "two", "three"] {
+        github
+            .repo("octocat", "hello-world")
+            .issues()
+            .create(&IssueOptions::new(
+                *title,
+                None::<String>,
+                None::<String>,
+                None,
+                Vec::<String>::new(),
+            ))
+            .await?;
+    }
+
+    let response = github
+        .with_response(|github| {
+            github
+                .repo("octocat", "hello-world")
+                .issues()
+                .list(&IssueListOptions::builder().per_page(2).build())
+        })
+        .await?;
+    assert_eq!(response.status.as_u16(), 200);
+    assert_eq!(response.entity.len(), 2);
+    assert!(response.next_url().unwrap().contains("page=2"));
+    assert_eq!(response.next_url(), response.last_url());
+    Ok(())
+}
+
+#[tokio::test]
+async fn tracks_pulls_and_statuses() -> Result<()> {
+    let fake = FakeGithub::start()?;
+    fake.create_repo("octocat", "hello-world");
+    let repo = github(&fake)?.repo("octocat", "hello-world");
+
+    let pull = repo
+        .pulls()
+        .create(&PullOptions::new(
+            "feature",
+            "octocat:feature",
+            "master",
+            None::<String>,
+        ))
+        .await?;
+    assert_eq!(pull.head.commit_ref, "feature");
+
+    repo.statuses()
+        .create(
+            &pull.head.sha,
+            &StatusOptions::builder(StatusState::Success)
+                .context("ci")
+                .build(),
+        )
+        .await?;
+    let statuses = repo.statuses().list(&pull.head.sha).await?;
+    assert_eq!(statuses[0].context, "ci");
+
+    repo.pulls()
+        .get(pull.number)
+        .edit(&PullEditOptions::builder().state("closed").build())
+        .await?;
+    let open = repo
+        .pulls()
+        .list(&PullListOptions::builder().build())
+        .await?;
+    assert!(open.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_repositories_are_not_found() -> Result<()> {
+    let fake = FakeGithub::start()?;
+    match github(&fake)?.repo("octocat", "missing").get().await {
+        Err(Error::Fault { code, .. }) => assert_eq!(code.as_u16(), 404),
+        otherwise => panic!("unexpected result {:?}", otherwise.map(|repo| repo.id)),
+    }
+    Ok(())
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "cassette")]
+
+use futures::prelude::*;
+use hubcaps::cassette::ReplayTransport;
+use hubcaps::{Credentials, Error, Github, Result};
+
+#[tokio::test]
+async fn replays_paginated_labels() -> Result<()> {
+    let transport = ReplayTransport::load("tests/cassettes/labels.json")?;
+    let mut github = Github::new(
+        concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")),
+        Credentials::Token("not-a-real-token".into()),
+    )?;
+    github.set_transport(transport.clone());
+
+    let labels = github
+        .repo("octocat", "hello-world")
+        .labels()
+        .iter()
+        .map_ok(|label| label.name)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    assert_eq!(labels, vec!["bug", "enhancement", "question"]);
+    assert!(transport.is_exhausted());
+    Ok(())
+}
+
+#[tokio::test]
+async fn fails_on_unmatched_requests() {
+    let transport = ReplayTransport::load("tests/cassettes/labels.json").unwrap();
+    let mut github = Github::new("hubcaps", None).unwrap();
+    github.set_transport(transport);
+
+    for _ in 0..2 {
+        match github.repo("octocat", "spoon-knife").labels().list().await {
+            Err(Error::IO(err)) => assert_eq!(
+                err.to_string(),
+                "no unused interaction in tests/cassettes/labels.json matches \
+                 GET https://api.github.com/repos/octocat/spoon-knife/labels"
+            ),
+            other => panic!("expected an unmatched interaction error, got {:?}", other),
+        }
+    }
+}